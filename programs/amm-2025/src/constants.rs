@@ -0,0 +1,21 @@
+// LP minted to the pool itself on the first deposit and never released,
+// keeps the LP supply from ever being small enough to inflate the share price
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+// smallest sqrt(x * y) a first deposit is allowed to bring
+pub const MINIMUM_INITIAL_LIQUIDITY: u64 = 10 * MINIMUM_LIQUIDITY;
+
+// bounds of the StableSwap amplification coefficient
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+
+// an A ramp runs for at least a day and moves A by at most 10x,
+// so LPs can react before the curve shape changes under them
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+// lightest side a weighted pool may have, in basis points, caps the pow exponent at 49
+pub const MIN_WEIGHT: u16 = 200;
+
+// widest tick spacing a concentrated liquidity pool can use
+pub const MAX_TICK_SPACING: u16 = 16_384;
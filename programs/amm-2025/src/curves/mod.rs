@@ -0,0 +1,311 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MINIMUM_LIQUIDITY, error::AMMErrorCode, math::{initial_liquidity, mul_div}};
+
+pub mod constant_product;
+pub use constant_product::*;
+pub mod stable_swap;
+pub use stable_swap::*;
+pub mod weighted;
+pub use weighted::*;
+pub mod oracle;
+pub use oracle::*;
+
+// 1.0 in the Q64.64 fixed point used for prices
+pub const Q64: u128 = 1 << 64;
+
+// each zap trial runs a full swap, the bisection stops once s is pinned to 2^-20 of the deposit
+pub const ZAP_PRECISION_BITS: u32 = 20;
+
+// invariant a pool prices with, fixed at initialize and part of the config seeds
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap,
+    Weighted,
+    Oracle,
+}
+
+// pricing of a pool built over its live reserves. amounts going in and out of a curve are
+// what moves between the vaults and the user, swap fees are charged around it by `swap_exact_in`/`swap_exact_out`
+pub trait Curve {
+    fn reserves(&self) -> (u64, u64);
+
+    // output of the opposite side for `amount_in` of x (or y), rounded down
+    fn amount_out(&self, x: bool, amount_in: u64) -> Result<u64>;
+
+    // input of x (or y) needed to take exactly `amount_out` of the opposite side, rounded up
+    fn amount_in(&self, x: bool, amount_out: u64) -> Result<u64>;
+
+    // marginal price of x in y, Q64.64
+    fn spot_price(&self) -> Result<u128>;
+
+    // x and y backing `lp` new LP tokens, rounded up
+    fn deposit_amounts(&self, supply: u64, lp: u64) -> Result<(u64, u64)> {
+        let (reserve_x, reserve_y) = self.reserves();
+        require!(supply > 0, AMMErrorCode::ZeroBalance);
+
+        let x = (lp as u128 * reserve_x as u128).div_ceil(supply as u128);
+        let y = (lp as u128 * reserve_y as u128).div_ceil(supply as u128);
+        Ok((
+            u64::try_from(x).map_err(|_| error!(AMMErrorCode::Overflow))?,
+            u64::try_from(y).map_err(|_| error!(AMMErrorCode::Overflow))?
+        ))
+    }
+
+    // x and y released by burning `lp` LP tokens, rounded down
+    fn withdraw_amounts(&self, supply: u64, lp: u64) -> Result<(u64, u64)> {
+        let (reserve_x, reserve_y) = self.reserves();
+        require!(lp <= supply, AMMErrorCode::InsufficientBalance);
+
+        Ok((
+            (lp as u128 * reserve_x as u128 / supply as u128) as u64,
+            (lp as u128 * reserve_y as u128 / supply as u128) as u64
+        ))
+    }
+
+    // part of a single sided deposit of x (or y) to swap so the rest matches the pool ratio.
+    // largest s with (a - s) / (r_in + s) >= out(s) / (r_out - out(s)), found by bisection.
+    // stops about ZAP_PRECISION_BITS trials in on the low side, the part of s it gives up stays unspent
+    fn zap_amount(&self, x: bool, amount_in: u64, fee: u16) -> Result<u64> {
+        require!(fee < 10000, AMMErrorCode::InvalidFee);
+        let (reserve_in, reserve_out) = match x {
+            true => self.reserves(),
+            false => (self.reserves().1, self.reserves().0)
+        };
+
+        let tolerance = amount_in >> ZAP_PRECISION_BITS;
+        let (mut low, mut high) = (0u64, amount_in);
+        while high - low > tolerance {
+            let s = high - (high - low) / 2;
+            // a trial the pool can not fill is past the balance point
+            let Ok(res) = swap_exact_in(self, x, s, fee) else {
+                high = s - 1;
+                continue;
+            };
+            let out = res.amount_out;
+            let balanced = out < reserve_out && (amount_in - s) as u128 * (reserve_out - out) as u128
+                >= out as u128 * (reserve_in as u128 + s as u128);
+            match balanced {
+                true => low = s,
+                false => high = s - 1
+            }
+        }
+        Ok(low)
+    }
+}
+
+pub struct SwapResult {
+    // what enters the vault, fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+}
+
+// the fee (in basis points) is taken from the input, only the rest is priced by the curve
+pub fn swap_exact_in<C: Curve + ?Sized>(curve: &C, x: bool, amount_in: u64, fee: u16) -> Result<SwapResult> {
+    require!(fee < 10000, AMMErrorCode::InvalidFee);
+
+    let fee = (amount_in as u128 * fee as u128).div_ceil(10000) as u64;
+    let amount_out = curve.amount_out(x, amount_in - fee)?;
+
+    Ok(SwapResult { amount_in, amount_out, fee })
+}
+
+// input for exactly `amount_out`, grossed up so the fee comes on top and rounding favours the pool
+pub fn swap_exact_out<C: Curve + ?Sized>(curve: &C, x: bool, amount_out: u64, fee: u16) -> Result<SwapResult> {
+    require!(fee < 10000, AMMErrorCode::InvalidFee);
+
+    let net_in = curve.amount_in(x, amount_out)?;
+    let amount_in = (net_in as u128 * 10000).div_ceil(10000 - fee as u128);
+    let amount_in = u64::try_from(amount_in).map_err(|_| error!(AMMErrorCode::Overflow))?;
+
+    Ok(SwapResult { amount_in, amount_out, fee: amount_in - net_in })
+}
+
+pub struct SingleWithdrawal {
+    // proportional share of the burnt LP
+    pub amount_x: u64,
+    pub amount_y: u64,
+    // the unwanted side swapped into the wanted one, None when that side rounded to zero
+    pub swap: Option<SwapResult>,
+}
+
+// LP the first deposit of x and y mints to the user. tokens sent to the vaults ahead of it
+// count towards its LP, MINIMUM_LIQUIDITY of which stays locked in the pool
+pub fn first_deposit_lp(reserve_x: u64, reserve_y: u64, x: u64, y: u64, min_lp: u64) -> Result<u64> {
+    let lp = initial_liquidity(
+        reserve_x.checked_add(x).ok_or(AMMErrorCode::Overflow)?,
+        reserve_y.checked_add(y).ok_or(AMMErrorCode::Overflow)?
+    )?
+    .checked_sub(MINIMUM_LIQUIDITY)
+    .ok_or(AMMErrorCode::Underflow)?;
+    require!(lp >= min_lp, AMMErrorCode::SlippageExceeded);
+    Ok(lp)
+}
+
+// burns `lp` for its share of both sides and swaps the side not wanted against the pool left
+// after the burn, exactly what a withdraw followed by a swap would do. `curve` builds the
+// pool's curve over a pair of reserves
+pub fn withdraw_single<F>(
+    curve: F,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    lp: u64,
+    to_x: bool,
+    fee: u16
+) -> Result<SingleWithdrawal>
+where
+    F: Fn(u64, u64) -> Result<Box<dyn Curve>>
+{
+    let (amount_x, amount_y) = curve(reserve_x, reserve_y)?.withdraw_amounts(supply, lp)?;
+
+    let burnt_x = reserve_x.checked_sub(amount_x).ok_or(AMMErrorCode::Underflow)?;
+    let burnt_y = reserve_y.checked_sub(amount_y).ok_or(AMMErrorCode::Underflow)?;
+    let swapped = match to_x {
+        true => amount_y,
+        false => amount_x
+    };
+
+    let swap = match swapped {
+        0 => None,
+        _ => Some(swap_exact_in(&*curve(burnt_x, burnt_y)?, !to_x, swapped, fee)?)
+    };
+    Ok(SingleWithdrawal { amount_x, amount_y, swap })
+}
+
+// y / x as Q64.64
+pub fn ratio_price(reserve_x: u64, reserve_y: u64) -> Result<u128> {
+    require!(reserve_x > 0, AMMErrorCode::ZeroBalance);
+    mul_div(reserve_y as u128, Q64, reserve_x as u128).ok_or(error!(AMMErrorCode::Overflow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant_product(reserve_x: u64, reserve_y: u64) -> Result<Box<dyn Curve>> {
+        Ok(Box::new(ConstantProduct { reserve_x, reserve_y }))
+    }
+
+    // every rounding has to land on the pool's side
+    fn assert_rounds_for_pool(curve: &dyn Curve, supply: u64) {
+        let (reserve_x, reserve_y) = curve.reserves();
+
+        for lp in [1u64, 7, 1_000, 123_456, supply / 3] {
+            let (x, y) = curve.deposit_amounts(supply, lp).unwrap();
+            assert!(x as u128 * supply as u128 >= lp as u128 * reserve_x as u128);
+            assert!(y as u128 * supply as u128 >= lp as u128 * reserve_y as u128);
+
+            let (x, y) = curve.withdraw_amounts(supply, lp).unwrap();
+            assert!(x as u128 * supply as u128 <= lp as u128 * reserve_x as u128);
+            assert!(y as u128 * supply as u128 <= lp as u128 * reserve_y as u128);
+        }
+
+        for fee in [0u16, 30, 1000] {
+            for x in [true, false] {
+                for amount in [10u64, 1_000, 1_000_000, 50_000_000] {
+                    let res = swap_exact_in(curve, x, amount, fee).unwrap();
+                    assert!(res.fee as u128 * 10000 >= amount as u128 * fee as u128);
+
+                    // trading the output straight back never returns more than was put in
+                    if res.amount_out > 0 {
+                        let back = swap_exact_in(curve, !x, res.amount_out, fee).unwrap();
+                        assert!(back.amount_out <= amount);
+                    }
+
+                    // paying what exact out asks for always buys at least that much
+                    let exact = swap_exact_out(curve, x, amount, fee).unwrap();
+                    assert!(swap_exact_in(curve, x, exact.amount_in, fee).unwrap().amount_out >= amount);
+                    assert!(curve.amount_out(x, curve.amount_in(x, amount).unwrap()).unwrap() >= amount);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn curves_round_for_the_pool() {
+        let supply = 2_000_000_000;
+        assert_rounds_for_pool(&ConstantProduct { reserve_x: 1_000_000_000, reserve_y: 3_000_000_000 }, supply);
+        assert_rounds_for_pool(&StableSwap { reserve_x: 1_000_000_000, reserve_y: 1_200_000_000, amp: 200 }, supply);
+        assert_rounds_for_pool(
+            &Weighted { reserve_x: 1_000_000_000, reserve_y: 3_000_000_000, weight_x: 8000, weight_y: 2000 },
+            supply
+        );
+        assert_rounds_for_pool(
+            &Oracle { reserve_x: 1_000_000_000, reserve_y: 3_000_000_000, price: 3 * Q64, spread: 10 },
+            supply
+        );
+    }
+
+    #[test]
+    fn withdraw_single_on_a_small_pool() {
+        // a tenth of a 1_000_000 x / 4_000_000 y pool, the burn leaves 900_000 / 3_600_000
+        let single = withdraw_single(constant_product, 1_000_000, 4_000_000, 2_000_000, 200_000, true, 30).unwrap();
+        assert_eq!((single.amount_x, single.amount_y), (100_000, 400_000));
+        // 1_200 of the 400_000 y is fee, 900_000 * 398_800 / 3_998_800 = 89_756.9
+        let swap = single.swap.unwrap();
+        assert_eq!((swap.amount_in, swap.fee, swap.amount_out), (400_000, 1_200, 89_756));
+
+        // 300 of the 100_000 x is fee, 3_600_000 * 99_700 / 999_700 = 359_027.7
+        let single = withdraw_single(constant_product, 1_000_000, 4_000_000, 2_000_000, 200_000, false, 30).unwrap();
+        let swap = single.swap.unwrap();
+        assert_eq!((swap.amount_in, swap.fee, swap.amount_out), (100_000, 300, 359_027));
+    }
+
+    // counts the swaps the default zap tries
+    struct Counted(Weighted, std::cell::Cell<u32>);
+
+    impl Curve for Counted {
+        fn reserves(&self) -> (u64, u64) {
+            self.0.reserves()
+        }
+
+        fn amount_out(&self, x: bool, amount_in: u64) -> Result<u64> {
+            self.1.set(self.1.get() + 1);
+            self.0.amount_out(x, amount_in)
+        }
+
+        fn amount_in(&self, x: bool, amount_out: u64) -> Result<u64> {
+            self.0.amount_in(x, amount_out)
+        }
+
+        fn spot_price(&self) -> Result<u128> {
+            self.0.spot_price()
+        }
+    }
+
+    #[test]
+    fn zap_bisection_is_bounded() {
+        for amount in [1_000u64, 1_000_000_000, u64::MAX / 4] {
+            let curve = Counted(Weighted { reserve_x: 1_000_000_000, reserve_y: 3_000_000_000, weight_x: 8000, weight_y: 2000 }, Default::default());
+            let s = curve.zap_amount(true, amount, 30).unwrap();
+            assert!(curve.1.get() <= ZAP_PRECISION_BITS + 1, "{} trials for {amount}", curve.1.get());
+
+            // the search gives up at most its precision, on the side that leaves input unspent
+            let out = swap_exact_in(&curve, true, s, 30).unwrap().amount_out;
+            assert!((amount - s) as u128 * (3_000_000_000 - out) as u128 >= out as u128 * (1_000_000_000 + s) as u128);
+            let over = s + (amount >> ZAP_PRECISION_BITS) + 1;
+            let out = swap_exact_in(&curve, true, over, 30).map_or(u64::MAX, |res| res.amount_out);
+            assert!(out >= 3_000_000_000 || ((amount - over) as u128 * (3_000_000_000 - out) as u128) < out as u128 * (1_000_000_000 + over) as u128);
+        }
+    }
+
+    #[test]
+    fn first_deposit_locks_the_minimum() {
+        assert_eq!(first_deposit_lp(0, 0, 4_000_000, 1_000_000, 1).unwrap(), 2_000_000 - MINIMUM_LIQUIDITY);
+        // a donation ahead of the first deposit is priced into its LP
+        assert_eq!(first_deposit_lp(3_000_000, 0, 1_000_000, 1_000_000, 1).unwrap(), 2_000_000 - MINIMUM_LIQUIDITY);
+        assert!(first_deposit_lp(0, 0, 4_000_000, 1_000_000, 2_000_000).is_err());
+        assert!(first_deposit_lp(0, 0, 1_000, 1_000, 1).is_err());
+    }
+
+    #[test]
+    fn withdraw_single_skips_an_empty_side() {
+        // a share too small to round to any y leaves nothing to swap, the x share is all there is
+        let single = withdraw_single(constant_product, 1_000_000_000, 10, 1_000_000_000, 1_000, true, 30).unwrap();
+        assert_eq!((single.amount_x, single.amount_y), (1_000, 0));
+        assert!(single.swap.is_none());
+    }
+}
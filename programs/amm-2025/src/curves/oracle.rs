@@ -0,0 +1,164 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AMMErrorCode, math::{mul_div, mul_div_ceil}};
+
+use super::{Curve, Q64};
+
+// trades at an external price: x is bought at price * (1 + spread) and sold at price * (1 - spread),
+// the reserves only cap what the pool can pay out
+pub struct Oracle {
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    // y per x, Q64.64
+    pub price: u128,
+    pub spread: u16,
+}
+
+impl Oracle {
+    fn reserve_out(&self, x: bool) -> u64 {
+        match x {
+            true => self.reserve_y,
+            false => self.reserve_x
+        }
+    }
+}
+
+impl Curve for Oracle {
+    fn reserves(&self) -> (u64, u64) {
+        (self.reserve_x, self.reserve_y)
+    }
+
+    fn amount_out(&self, x: bool, amount_in: u64) -> Result<u64> {
+        require!(self.price > 0, AMMErrorCode::InvalidOracle);
+        let spread = self.spread as u128;
+
+        let amount_out = match x {
+            // x in at the bid
+            true => mul_div(amount_in as u128, self.price, Q64)
+                .and_then(|y| mul_div(y, 10000 - spread, 10000)),
+            // y in at the ask
+            false => mul_div(amount_in as u128, Q64, self.price)
+                .and_then(|x| mul_div(x, 10000, 10000 + spread))
+        }.ok_or(AMMErrorCode::Overflow)?;
+
+        require!(amount_out < self.reserve_out(x) as u128, AMMErrorCode::InsufficientBalance);
+        Ok(amount_out as u64)
+    }
+
+    fn amount_in(&self, x: bool, amount_out: u64) -> Result<u64> {
+        require!(self.price > 0, AMMErrorCode::InvalidOracle);
+        require!(amount_out < self.reserve_out(x), AMMErrorCode::InsufficientBalance);
+        let spread = self.spread as u128;
+
+        let amount_in = match x {
+            true => mul_div_ceil(amount_out as u128, 10000, 10000 - spread)
+                .and_then(|y| mul_div_ceil(y, Q64, self.price)),
+            false => mul_div_ceil(amount_out as u128, 10000 + spread, 10000)
+                .and_then(|x| mul_div_ceil(x, self.price, Q64))
+        }.ok_or(AMMErrorCode::Overflow)?;
+
+        u64::try_from(amount_in).map_err(|_| error!(AMMErrorCode::Overflow))
+    }
+
+    fn spot_price(&self) -> Result<u128> {
+        Ok(self.price)
+    }
+
+    // output is linear in the input at rate k (price, spread and fee in), so
+    // (a - s) / (r_in + s) = k s / (r_out - k s) solves to s = a * r_out / (r_out + k * (a + r_in))
+    fn zap_amount(&self, x: bool, amount_in: u64, fee: u16) -> Result<u64> {
+        require!(self.price > 0, AMMErrorCode::InvalidOracle);
+        require!(fee < 10000, AMMErrorCode::InvalidFee);
+        let (spread, fee) = (self.spread as u128, fee as u128);
+
+        let (reserve_in, reserve_out) = match x {
+            true => (self.reserve_x, self.reserve_y),
+            false => (self.reserve_y, self.reserve_x)
+        };
+        // out per unit in, Q64.64
+        let rate = match x {
+            true => mul_div(self.price, (10000 - spread) * (10000 - fee), 100_000_000),
+            false => mul_div(Q64, Q64, self.price).and_then(|inverse| mul_div(inverse, 10000 - fee, 10000 + spread))
+        }.ok_or(AMMErrorCode::Overflow)?;
+
+        let denominator = mul_div(rate, amount_in as u128 + reserve_in as u128, Q64)
+            .and_then(|v| v.checked_add(reserve_out as u128))
+            .ok_or(AMMErrorCode::Overflow)?;
+        require!(denominator > 0, AMMErrorCode::ZeroBalance);
+
+        let swap = mul_div(amount_in as u128, reserve_out as u128, denominator).ok_or(AMMErrorCode::Overflow)?;
+        Ok((swap as u64).min(amount_in))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::{swap_exact_in, ZAP_PRECISION_BITS};
+
+    // the oracle curve priced through the trait's default bisection zap
+    struct Bisected(Oracle);
+
+    impl Curve for Bisected {
+        fn reserves(&self) -> (u64, u64) {
+            self.0.reserves()
+        }
+
+        fn amount_out(&self, x: bool, amount_in: u64) -> Result<u64> {
+            self.0.amount_out(x, amount_in)
+        }
+
+        fn amount_in(&self, x: bool, amount_out: u64) -> Result<u64> {
+            self.0.amount_in(x, amount_out)
+        }
+
+        fn spot_price(&self) -> Result<u128> {
+            self.0.spot_price()
+        }
+    }
+
+    fn pool(reserve_x: u64, reserve_y: u64, price: u128) -> Oracle {
+        Oracle { reserve_x, reserve_y, price, spread: 20 }
+    }
+
+    #[test]
+    fn zaps_into_a_skewed_pool() {
+        // a hundred times more x than y at price 1, most trial swaps of the bisection can not be filled
+        let curve = pool(1_000_000_000, 10_000_000, Q64);
+        let amount = 100_000_000;
+
+        let swap = curve.zap_amount(true, amount, 30).unwrap();
+        let out = swap_exact_in(&curve, true, swap, 30).unwrap().amount_out;
+        // what is left of the deposit matches the pool after the swap
+        let left = (amount - swap) as f64 / (1_000_000_000 + swap) as f64;
+        let ratio = out as f64 / (10_000_000 - out) as f64;
+        assert!((left - ratio).abs() / ratio < 1e-4);
+
+        let bisected = Bisected(pool(1_000_000_000, 10_000_000, Q64)).zap_amount(true, amount, 30).unwrap();
+        assert!(swap.abs_diff(bisected) <= 2 + bisected / 100_000 + (amount >> ZAP_PRECISION_BITS), "{swap} vs {bisected}");
+    }
+
+    #[test]
+    fn closed_form_zap_matches_bisection() {
+        let pools = [(1_000_000_000u64, 1_000_000_000u64, Q64), (1_000_000_000, 10_000_000, Q64), (5_000_000, 20_000_000_000, 4_000 * Q64)];
+
+        for (reserve_x, reserve_y, price) in pools {
+            for amount in [1_000_000u64, 77_777_777, 1_000_000_000] {
+                for fee in [0u16, 30, 1000] {
+                    for x in [true, false] {
+                        // the bisection balances floored outputs, with few output atoms that moves s by more than the tolerance
+                        if pool(reserve_x, reserve_y, price).amount_out(x, amount).is_ok_and(|out| out < 1_000_000) {
+                            continue;
+                        }
+                        let closed = pool(reserve_x, reserve_y, price).zap_amount(x, amount, fee).unwrap();
+                        let bisected = Bisected(pool(reserve_x, reserve_y, price)).zap_amount(x, amount, fee).unwrap();
+                        assert!(
+                            closed.abs_diff(bisected) <= 2 + bisected / 100_000 + (amount >> ZAP_PRECISION_BITS),
+                            "pool {reserve_x}/{reserve_y} amount {amount} fee {fee} x {x}: {closed} vs {bisected}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
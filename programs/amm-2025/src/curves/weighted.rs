@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AMMErrorCode, math::{mul_div, pow_q64}};
+
+use super::{Curve, Q64};
+
+// Balancer-style constant mean x^wx * y^wy = k, weights in basis points summing to 10000.
+// at equilibrium the reserves hold wx : wy of the pool value, proportional deposits keep that split
+pub struct Weighted {
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub weight_x: u16,
+    pub weight_y: u16,
+}
+
+impl Weighted {
+    fn sides(&self, x: bool) -> (u64, u64, u64, u64) {
+        match x {
+            true => (self.reserve_x, self.reserve_y, self.weight_x as u64, self.weight_y as u64),
+            false => (self.reserve_y, self.reserve_x, self.weight_y as u64, self.weight_x as u64)
+        }
+    }
+}
+
+impl Curve for Weighted {
+    fn reserves(&self) -> (u64, u64) {
+        (self.reserve_x, self.reserve_y)
+    }
+
+    // dy = y * (1 - (x / (x + dx))^(wx / wy)), the power is rounded up so dy rounds down
+    fn amount_out(&self, x: bool, amount_in: u64) -> Result<u64> {
+        let (reserve_in, reserve_out, weight_in, weight_out) = self.sides(x);
+        require!(reserve_in > 0 && reserve_out > 0, AMMErrorCode::ZeroBalance);
+
+        let base = ((reserve_in as u128) << 64).div_ceil(reserve_in as u128 + amount_in as u128);
+        let ratio = pow_q64(base, weight_in, weight_out, true).ok_or(AMMErrorCode::Overflow)?;
+
+        let amount_out = mul_div(reserve_out as u128, Q64 - ratio, Q64).ok_or(AMMErrorCode::Overflow)?;
+        Ok(amount_out as u64)
+    }
+
+    // dx = x * ((y / (y - dy))^(wy / wx) - 1), taken through the inverse so the base stays below one
+    fn amount_in(&self, x: bool, amount_out: u64) -> Result<u64> {
+        let (reserve_in, reserve_out, weight_in, weight_out) = self.sides(x);
+        require!(amount_out < reserve_out, AMMErrorCode::InsufficientBalance);
+
+        let base = ((reserve_out - amount_out) as u128) << 64;
+        let ratio = pow_q64(base / reserve_out as u128, weight_out, weight_in, false).ok_or(AMMErrorCode::Overflow)?;
+        require!(ratio > 0, AMMErrorCode::InsufficientBalance);
+
+        let amount_in = (reserve_in as u128 * (Q64 - ratio)).div_ceil(ratio);
+        u64::try_from(amount_in).map_err(|_| error!(AMMErrorCode::Overflow))
+    }
+
+    // (y / wy) / (x / wx)
+    fn spot_price(&self) -> Result<u128> {
+        require!(self.reserve_x > 0, AMMErrorCode::ZeroBalance);
+
+        mul_div(
+            self.reserve_y as u128 * self.weight_x as u128,
+            Q64,
+            self.reserve_x as u128 * self.weight_y as u128
+        ).ok_or(error!(AMMErrorCode::Overflow))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESERVES: [(u64, u64); 4] = [
+        (1_000_000, 1_000_000),
+        (4_000_000_000, 1_000_000_000),
+        (1_000_000_000_000, 7_000),
+        (u64::MAX / 4, u64::MAX / 2),
+    ];
+
+    fn pool(reserve_x: u64, reserve_y: u64) -> Weighted {
+        Weighted { reserve_x, reserve_y, weight_x: 8000, weight_y: 2000 }
+    }
+
+    #[test]
+    fn exact_out_covers_what_it_asks_for() {
+        for (x, y) in RESERVES {
+            let pool = pool(x, y);
+            for side in [true, false] {
+                let reserve_out = pool.sides(side).1;
+                for amount in [1, 1_000, reserve_out / 100, reserve_out / 2] {
+                    if amount == 0 || amount >= reserve_out {
+                        continue;
+                    }
+                    // the heavy side is worth a lot less per unit, some inputs do not fit a u64
+                    let Ok(amount_in) = pool.amount_in(side, amount) else {
+                        continue;
+                    };
+                    let out = pool.amount_out(side, amount_in).unwrap();
+                    assert!(out >= amount, "pool {x}/{y} side {side} amount {amount}: in {amount_in} out {out}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exact_in_never_asks_for_more() {
+        for (x, y) in RESERVES {
+            let pool = pool(x, y);
+            for side in [true, false] {
+                let reserve_in = pool.sides(side).0;
+                for amount in [1, 1_000, reserve_in / 100, reserve_in] {
+                    let out = pool.amount_out(side, amount).unwrap();
+                    if out == 0 {
+                        continue;
+                    }
+                    let amount_in = pool.amount_in(side, out).unwrap();
+                    assert!(amount_in <= amount, "pool {x}/{y} side {side} amount {amount}: out {out} in {amount_in}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn eighty_twenty_holds_value_by_weight() {
+        // 4:1 reserves on 80/20 weights price x and y the same
+        let pool = pool(4_000_000_000, 1_000_000_000);
+        assert_eq!(pool.spot_price().unwrap(), Q64);
+
+        // and a small trade either way fills close to that price, never above it
+        let out = pool.amount_out(true, 1_000).unwrap();
+        assert!((990..=1_000).contains(&out));
+        let out = pool.amount_out(false, 1_000).unwrap();
+        assert!((990..=1_000).contains(&out));
+    }
+}
@@ -1,5 +1,4 @@
 use anchor_lang::prelude::*;
-use constant_product_curve::CurveError;
 
 #[error_code]
 pub enum AMMErrorCode {
@@ -27,19 +26,54 @@ pub enum AMMErrorCode {
     InsufficientBalance,
     #[msg("slippage limit exceeded")]
     SlippageExceeded,
+    #[msg("no treasury is set")]
+    NoTreasurySet,
+    #[msg("fee delay can not be shortened")]
+    InvalidFeeDelay,
+    #[msg("invalid volatility parameters")]
+    InvalidVolatilityParams,
+    #[msg("mints must be passed in canonical order")]
+    InvalidMintOrder,
+    #[msg("mint x and mint y are the same")]
+    IdenticalMints,
+    #[msg("first deposit is below the minimum liquidity")]
+    InsufficientInitialLiquidity,
+    #[msg("mint has an unsupported token extension")]
+    UnsupportedMintExtension,
+    #[msg("neither pool mint is wrapped SOL")]
+    NoNativeMint,
+    #[msg("amplification coefficient out of range")]
+    InvalidAmp,
+    #[msg("invalid amplification ramp")]
+    InvalidAmpRamp,
+    #[msg("not supported by this pool's curve")]
+    InvalidCurveType,
+    #[msg("stable pools need mints with the same decimals")]
+    DecimalsMismatch,
+    #[msg("curve invariant did not converge")]
+    InvariantNotConverged,
+    #[msg("pool weights out of range")]
+    InvalidWeights,
+    #[msg("tick out of range or off the tick spacing")]
+    InvalidTick,
+    #[msg("sqrt price out of range")]
+    InvalidSqrtPrice,
+    #[msg("invalid tick spacing")]
+    InvalidTickSpacing,
+    #[msg("ticks are not the neighbours of the new tick")]
+    InvalidTickNeighbors,
+    #[msg("a tick the swap crosses was not passed")]
+    MissingTickAccount,
+    #[msg("tick account does not match the tick being crossed")]
+    InvalidTickAccount,
+    #[msg("position still holds liquidity or fees")]
+    PositionNotEmpty,
+    #[msg("price feed account is not the pool's oracle or can not be read")]
+    InvalidOracle,
+    #[msg("oracle confidence interval is too wide")]
+    OracleConfidence,
+    #[msg("oracle price is stale")]
+    StaleOracle,
+    #[msg("invalid oracle parameters")]
+    InvalidOracleParams,
 }
-
-
-impl From<CurveError> for AMMErrorCode {
-    fn from(error: CurveError) -> AMMErrorCode {
-        match error {
-            CurveError::InvalidPrecision => AMMErrorCode::InvalidPrecision,
-            CurveError::Overflow => AMMErrorCode::Overflow,
-            CurveError::Underflow => AMMErrorCode::Underflow,
-            CurveError::InvalidFeeAmount => AMMErrorCode::InvalidFee,
-            CurveError::InsufficientBalance => AMMErrorCode::InsufficientBalance,
-            CurveError::ZeroBalance => AMMErrorCode::ZeroBalance,
-            CurveError::SlippageLimitExceeded => AMMErrorCode::SlippageExceeded,
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::CurveType;
+
+#[event]
+pub struct PoolInitialized {
+    pub config: Pubkey,
+    pub initializer: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub mint_lp: Pubkey,
+    pub fee_tier: Pubkey,
+    pub curve_type: CurveType,
+    pub amp: u64,
+    pub weight_x: u16,
+    pub weight_y: u16,
+    pub oracle: Pubkey,
+    pub spread: u16,
+    pub fee: u16,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub amount_lp: u64,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub amount_lp: u64,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+#[event]
+pub struct Swapped {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    // true when x was the input side
+    pub x: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub protocol_fee: u64,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+#[event]
+pub struct PoolLocked {
+    pub config: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PoolUnlocked {
+    pub config: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub pending_authority: Option<Pubkey>,
+    pub fee: u16,
+    pub max_fee: u16,
+    pub fee_delay: i64,
+    pub pending_fee: Option<u16>,
+    pub fee_effective_at: i64,
+    pub dynamic_fee: bool,
+    pub base_fee: u16,
+    pub max_dynamic_fee: u16,
+    pub protocol_fee: u16,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct ProtocolFeesCollected {
+    pub config: Pubkey,
+    pub treasury: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[event]
+pub struct FeeTierCreated {
+    pub fee_tier: Pubkey,
+    pub admin: Pubkey,
+    pub fee: u16,
+}
+
+#[event]
+pub struct AmpRampUpdated {
+    pub config: Pubkey,
+    pub authority: Pubkey,
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub amp_start_ts: i64,
+    pub amp_stop_ts: i64,
+}
+
+#[event]
+pub struct ClPoolInitialized {
+    pub pool: Pubkey,
+    pub initializer: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub fee_tier: Pubkey,
+    pub fee: u16,
+    pub tick_spacing: u16,
+    pub sqrt_price: u128,
+    pub tick: i32,
+}
+
+#[event]
+pub struct ClLiquidityChanged {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    // positive when liquidity was added
+    pub liquidity_delta: i128,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[event]
+pub struct ClFeesCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[event]
+pub struct ClSwapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    // true when x was the input side
+    pub x: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub sqrt_price: u128,
+    pub tick: i32,
+    pub liquidity: u128,
+}
+
+#[event]
+pub struct ClPoolLocked {
+    pub pool: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct ClPoolUnlocked {
+    pub pool: Pubkey,
+    pub admin: Pubkey,
+}
+
+// refresh_oracle found the pool's feed stale and locked it, it trades again once unlocked
+#[event]
+pub struct OracleStale {
+    pub config: Pubkey,
+    pub oracle: Pubkey,
+    pub user: Pubkey,
+}
@@ -0,0 +1,290 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked}};
+
+use crate::{
+    assert_not_expired, assert_not_locked,
+    error::AMMErrorCode,
+    events::{ClFeesCollected, ClLiquidityChanged},
+    state::{cl_pool::add_liquidity, ClPool, Position, Tick},
+    tick_math::{amounts_for_liquidity, sqrt_price_at_tick},
+    token::{amount_after_fee, amount_before_fee},
+};
+
+// adds or removes a position's liquidity and pays out its fees
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[
+            b"cl_pool",
+            pool.mint_x.as_ref(),
+            pool.mint_y.as_ref(),
+            pool.fee_tier.as_ref(),
+            pool.tick_spacing.to_le_bytes().as_ref()
+        ],
+        bump=pool.pool_bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: signs for the vaults, never holds data
+    #[account(seeds=[b"auth", pool.key().as_ref()], bump=pool.auth_bump)]
+    pub auth: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one=owner,
+        has_one=pool,
+        seeds=[
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref()
+        ],
+        bump=position.bump
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    #[account(
+        mut,
+        seeds=[b"tick", pool.key().as_ref(), position.tick_lower.to_le_bytes().as_ref()],
+        bump=tick_lower.bump
+    )]
+    pub tick_lower: Box<Account<'info, Tick>>,
+    #[account(
+        mut,
+        seeds=[b"tick", pool.key().as_ref(), position.tick_upper.to_le_bytes().as_ref()],
+        bump=tick_upper.bump
+    )]
+    pub tick_upper: Box<Account<'info, Tick>>,
+
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint=mint_x,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program_x,
+    )]
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint=mint_y,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program_y,
+    )]
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> ModifyLiquidity<'info> {
+    pub fn increase_liquidity(
+        &mut self,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64
+    ) -> Result<()> {
+        assert_not_locked!(self.pool.locked);
+        assert_not_expired!(expiration);
+        require!(liquidity > 0, AMMErrorCode::ZeroBalance);
+
+        let delta = i128::try_from(liquidity).map_err(|_| error!(AMMErrorCode::Overflow))?;
+        let (x, y) = self.modify_liquidity(delta)?;
+
+        // the vaults have to receive the full amounts, transfer fees come on top
+        let (x, y) = (amount_before_fee(&self.mint_x, x)?, amount_before_fee(&self.mint_y, y)?);
+
+        // Check for slippage
+        require!(x <= max_x && y <= max_y, AMMErrorCode::SlippageExceeded);
+
+        self.deposit_token(true, x)?;
+        self.deposit_token(false, y)?;
+
+        emit!(ClLiquidityChanged {
+            pool: self.pool.key(),
+            position: self.position.key(),
+            owner: self.owner.key(),
+            liquidity_delta: delta,
+            amount_x: x,
+            amount_y: y,
+        });
+        Ok(())
+    }
+
+    pub fn decrease_liquidity(
+        &mut self,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64
+    ) -> Result<()> {
+        assert_not_locked!(self.pool.locked);
+        assert_not_expired!(expiration);
+        require!(liquidity > 0, AMMErrorCode::ZeroBalance);
+        require!(liquidity <= self.position.liquidity, AMMErrorCode::InsufficientBalance);
+
+        let delta = i128::try_from(liquidity).map_err(|_| error!(AMMErrorCode::Overflow))?;
+        let (x, y) = self.modify_liquidity(-delta)?;
+
+        // Check for slippage on what reaches the owner
+        require!(
+            amount_after_fee(&self.mint_x, x)? >= min_x && amount_after_fee(&self.mint_y, y)? >= min_y,
+            AMMErrorCode::SlippageExceeded
+        );
+
+        self.withdraw_token(true, x)?;
+        self.withdraw_token(false, y)?;
+
+        emit!(ClLiquidityChanged {
+            pool: self.pool.key(),
+            position: self.position.key(),
+            owner: self.owner.key(),
+            liquidity_delta: -delta,
+            amount_x: x,
+            amount_y: y,
+        });
+        Ok(())
+    }
+
+    pub fn collect_position_fees(&mut self) -> Result<()> {
+        let (inside_x, inside_y) = self.pool.fee_growth_inside(&self.tick_lower, &self.tick_upper);
+        self.position.settle_fees(inside_x, inside_y)?;
+
+        let (x, y) = (self.position.fees_owed_x, self.position.fees_owed_y);
+        require!(x > 0 || y > 0, AMMErrorCode::ZeroBalance);
+
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+
+        self.withdraw_token(true, x)?;
+        self.withdraw_token(false, y)?;
+
+        emit!(ClFeesCollected {
+            pool: self.pool.key(),
+            position: self.position.key(),
+            owner: self.owner.key(),
+            amount_x: x,
+            amount_y: y,
+        });
+        Ok(())
+    }
+
+    // settles fees, moves `delta` liquidity through the position, its ticks and (when in range)
+    // the pool, and returns the token amounts behind it rounded in the pool's favour
+    pub fn modify_liquidity(&mut self, delta: i128) -> Result<(u64, u64)> {
+        let (inside_x, inside_y) = self.pool.fee_growth_inside(&self.tick_lower, &self.tick_upper);
+        self.position.settle_fees(inside_x, inside_y)?;
+
+        self.position.liquidity = add_liquidity(self.position.liquidity, delta)?;
+
+        self.tick_lower.liquidity_gross = add_liquidity(self.tick_lower.liquidity_gross, delta)?;
+        self.tick_lower.liquidity_net = self.tick_lower.liquidity_net
+            .checked_add(delta)
+            .ok_or(AMMErrorCode::Overflow)?;
+        self.tick_upper.liquidity_gross = add_liquidity(self.tick_upper.liquidity_gross, delta)?;
+        self.tick_upper.liquidity_net = self.tick_upper.liquidity_net
+            .checked_sub(delta)
+            .ok_or(AMMErrorCode::Overflow)?;
+
+        let (lower, upper) = (self.tick_lower.index, self.tick_upper.index);
+        if lower <= self.pool.tick_current && self.pool.tick_current < upper {
+            self.pool.liquidity = add_liquidity(self.pool.liquidity, delta)?;
+        }
+
+        amounts_for_liquidity(
+            self.pool.sqrt_price,
+            sqrt_price_at_tick(lower)?,
+            sqrt_price_at_tick(upper)?,
+            delta.unsigned_abs(),
+            delta > 0
+        )
+    }
+
+    pub fn deposit_token(
+        &mut self,
+        x: bool,
+        amount: u64
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to, mint, decimals, token_program) = match x {
+            true => (self.user_x.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.vault_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info())
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.owner.to_account_info()
+        };
+
+        let cpi_ctx = CpiContext::new(token_program, cpi_accounts);
+        transfer_checked(cpi_ctx, amount, decimals)
+    }
+
+    pub fn withdraw_token(
+        &mut self,
+        x: bool,
+        amount: u64
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to, mint, decimals, token_program) = match x {
+            true => (self.vault_x.to_account_info(), self.user_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.user_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info())
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.auth.to_account_info()
+        };
+
+        let pool_key = self.pool.key();
+        let seeds = &[
+            &b"auth"[..],
+            pool_key.as_ref(),
+            &[self.pool.auth_bump]
+        ];
+
+        let signer_seeds = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+        transfer_checked(cpi_ctx, amount, decimals)
+    }
+}
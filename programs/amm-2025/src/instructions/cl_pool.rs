@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::{
+    constants::MAX_TICK_SPACING,
+    error::AMMErrorCode,
+    events::ClPoolInitialized,
+    state::{ClPool, FeeTier},
+    tick_math::tick_at_sqrt_price,
+    token::assert_supported_mint,
+};
+
+#[derive(Accounts)]
+#[instruction(tick_spacing: u16)]
+pub struct CreateClPool<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program=token_program_y,
+        constraint = mint_y.key() != mint_x.key() @ AMMErrorCode::IdenticalMints,
+        constraint = mint_x.key() < mint_y.key() @ AMMErrorCode::InvalidMintOrder
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds=[b"fee_tier", fee_tier.fee.to_le_bytes().as_ref()],
+        bump=fee_tier.bump
+    )]
+    pub fee_tier: Account<'info, FeeTier>,
+
+    #[account(
+        init,
+        payer=initializer,
+        seeds=[
+            b"cl_pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+            fee_tier.key().as_ref(),
+            tick_spacing.to_le_bytes().as_ref()
+        ],
+        bump,
+        space= 8 + ClPool::INIT_SPACE
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: signs for the vaults, never holds data
+    #[account(seeds=[b"auth", pool.key().as_ref()], bump)]
+    pub auth: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint=mint_x,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint=mint_y,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> CreateClPool<'info> {
+    pub fn create_cl_pool(
+        &mut self,
+        bumps: CreateClPoolBumps,
+        tick_spacing: u16,
+        sqrt_price: u128
+    ) -> Result<()> {
+        assert_supported_mint(&self.mint_x)?;
+        assert_supported_mint(&self.mint_y)?;
+        require!((1..=MAX_TICK_SPACING).contains(&tick_spacing), AMMErrorCode::InvalidTickSpacing);
+
+        let fee = self.fee_tier.fee;
+        require!(fee < 10000, AMMErrorCode::InvalidFee);
+        let tick = tick_at_sqrt_price(sqrt_price)?;
+
+        self.pool.set_inner(ClPool {
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee_tier: self.fee_tier.key(),
+            fee,
+            tick_spacing,
+            sqrt_price,
+            tick_current: tick,
+            liquidity: 0,
+            tick_below: None,
+            tick_above: None,
+            fee_growth_x: 0,
+            fee_growth_y: 0,
+            locked: false,
+            auth_bump: bumps.auth,
+            pool_bump: bumps.pool
+        });
+
+        emit!(ClPoolInitialized {
+            pool: self.pool.key(),
+            initializer: self.initializer.key(),
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee_tier: self.fee_tier.key(),
+            fee,
+            tick_spacing,
+            sqrt_price,
+            tick,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AMMErrorCode,
+    state::{ClPool, Position},
+    tick_math::{MAX_TICK, MIN_TICK},
+};
+
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds=[
+            b"cl_pool",
+            pool.mint_x.as_ref(),
+            pool.mint_y.as_ref(),
+            pool.fee_tier.as_ref(),
+            pool.tick_spacing.to_le_bytes().as_ref()
+        ],
+        bump=pool.pool_bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    #[account(
+        init,
+        payer=owner,
+        seeds=[
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            tick_lower.to_le_bytes().as_ref(),
+            tick_upper.to_le_bytes().as_ref()
+        ],
+        bump,
+        space= 8 + Position::INIT_SPACE
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> OpenPosition<'info> {
+    pub fn open_position(&mut self, bumps: OpenPositionBumps, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        let spacing = self.pool.tick_spacing as i32;
+        require!(
+            MIN_TICK <= tick_lower && tick_lower < tick_upper && tick_upper <= MAX_TICK,
+            AMMErrorCode::InvalidTick
+        );
+        require!(tick_lower % spacing == 0 && tick_upper % spacing == 0, AMMErrorCode::InvalidTick);
+
+        self.position.set_inner(Position {
+            pool: self.pool.key(),
+            owner: self.owner.key(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_x: 0,
+            fee_growth_inside_y: 0,
+            fees_owed_x: 0,
+            fees_owed_y: 0,
+            bump: bumps.position
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close=owner,
+        has_one=owner,
+        seeds=[
+            b"position",
+            position.pool.as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref()
+        ],
+        bump=position.bump
+    )]
+    pub position: Box<Account<'info, Position>>,
+}
+
+impl<'info> ClosePosition<'info> {
+    // only empty positions can go, liquidity and fees have to be taken out first
+    pub fn close_position(&mut self) -> Result<()> {
+        require!(
+            self.position.liquidity == 0 && self.position.fees_owed_x == 0 && self.position.fees_owed_y == 0,
+            AMMErrorCode::PositionNotEmpty
+        );
+        Ok(())
+    }
+}
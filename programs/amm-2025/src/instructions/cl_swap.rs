@@ -0,0 +1,237 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked}};
+
+use crate::{
+    assert_non_zero, assert_not_expired, assert_not_locked,
+    curves::Q64,
+    error::AMMErrorCode,
+    events::ClSwapped,
+    math::mul_div,
+    state::{ClPool, Tick},
+    tick_math::{sqrt_price_at_tick, swap_step, tick_at_sqrt_price, MAX_SQRT_PRICE, MIN_SQRT_PRICE},
+    token::{amount_after_fee, amount_before_fee},
+};
+
+// exact input swap against a concentrated liquidity pool. every tick the price crosses has to be
+// passed, writable and in crossing order, as a remaining account
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[
+            b"cl_pool",
+            pool.mint_x.as_ref(),
+            pool.mint_y.as_ref(),
+            pool.fee_tier.as_ref(),
+            pool.tick_spacing.to_le_bytes().as_ref()
+        ],
+        bump=pool.pool_bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: signs for the vaults, never holds data
+    #[account(seeds=[b"auth", pool.key().as_ref()], bump=pool.auth_bump)]
+    pub auth: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer=user,
+        associated_token::mint=mint_x,
+        associated_token::authority=user,
+        associated_token::token_program=token_program_x,
+    )]
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer=user,
+        associated_token::mint=mint_y,
+        associated_token::authority=user,
+        associated_token::token_program=token_program_y,
+    )]
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> ClSwap<'info> {
+    // x in pushes the price down, y in pushes it up. the swap stops early at `sqrt_price_limit`
+    // and only the consumed part of `amount` is taken
+    pub fn cl_swap(
+        &mut self,
+        ticks: &'info [AccountInfo<'info>],
+        x: bool,
+        amount: u64,
+        min: u64,
+        sqrt_price_limit: u128,
+        expiration: i64
+    ) -> Result<()> {
+        assert_not_locked!(self.pool.locked);
+        assert_not_expired!(expiration);
+        assert_non_zero!([amount]);
+
+        let valid_limit = match x {
+            true => MIN_SQRT_PRICE <= sqrt_price_limit && sqrt_price_limit < self.pool.sqrt_price,
+            false => self.pool.sqrt_price < sqrt_price_limit && sqrt_price_limit <= MAX_SQRT_PRICE
+        };
+        require!(valid_limit, AMMErrorCode::InvalidSqrtPrice);
+
+        let (mint_in, mint_out) = match x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x)
+        };
+        let received = amount_after_fee(mint_in, amount)?;
+
+        let mut remaining = received;
+        let mut amount_out: u64 = 0;
+        let mut fee: u64 = 0;
+        let mut ticks = ticks.iter();
+
+        while remaining > 0 && self.pool.sqrt_price != sqrt_price_limit {
+            let next_tick = match x {
+                true => self.pool.tick_below,
+                false => self.pool.tick_above
+            };
+            let tick_price = next_tick.map(sqrt_price_at_tick).transpose()?;
+            let target = match (tick_price, x) {
+                (Some(price), true) => price.max(sqrt_price_limit),
+                (Some(price), false) => price.min(sqrt_price_limit),
+                (None, _) => sqrt_price_limit
+            };
+
+            let step = swap_step(self.pool.sqrt_price, target, self.pool.liquidity, remaining, self.pool.fee, x)?;
+            remaining -= step.amount_in + step.fee;
+            amount_out = amount_out.checked_add(step.amount_out).ok_or(AMMErrorCode::Overflow)?;
+            fee = fee.checked_add(step.fee).ok_or(AMMErrorCode::Overflow)?;
+
+            // fees go to the liquidity that was active during the step
+            if self.pool.liquidity > 0 {
+                let growth = mul_div(step.fee as u128, Q64, self.pool.liquidity).ok_or(AMMErrorCode::Overflow)?;
+                match x {
+                    true => self.pool.fee_growth_x = self.pool.fee_growth_x.wrapping_add(growth),
+                    false => self.pool.fee_growth_y = self.pool.fee_growth_y.wrapping_add(growth)
+                }
+            }
+
+            self.pool.sqrt_price = step.sqrt_price;
+            match (next_tick, tick_price == Some(step.sqrt_price)) {
+                (Some(index), true) => {
+                    let info = ticks.next().ok_or(AMMErrorCode::MissingTickAccount)?;
+                    require!(info.is_writable, AMMErrorCode::InvalidTickAccount);
+
+                    let mut tick = Account::<Tick>::try_from(info)?;
+                    require!(
+                        tick.pool == self.pool.key() && tick.index == index,
+                        AMMErrorCode::InvalidTickAccount
+                    );
+
+                    self.pool.cross(&mut tick, !x)?;
+                    tick.exit(&crate::ID)?;
+                },
+                _ => self.pool.tick_current = tick_at_sqrt_price(step.sqrt_price)?
+            }
+        }
+
+        assert_non_zero!([amount_out]);
+        require!(amount_after_fee(mint_out, amount_out)? >= min, AMMErrorCode::SlippageExceeded);
+
+        // a swap cut short by the limit only pays for the input it used
+        let amount_in = match remaining {
+            0 => amount,
+            _ => amount_before_fee(mint_in, received - remaining)?
+        };
+
+        self.deposit_token(x, amount_in)?;
+        self.withdraw_token(!x, amount_out)?;
+
+        emit!(ClSwapped {
+            pool: self.pool.key(),
+            user: self.user.key(),
+            x,
+            amount_in,
+            amount_out,
+            fee,
+            sqrt_price: self.pool.sqrt_price,
+            tick: self.pool.tick_current,
+            liquidity: self.pool.liquidity,
+        });
+        Ok(())
+    }
+
+    pub fn deposit_token(
+        &mut self,
+        x: bool,
+        amount: u64
+    ) -> Result<()> {
+        let (from, to, mint, decimals, token_program) = match x {
+            true => (self.user_x.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.vault_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info())
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.user.to_account_info()
+        };
+
+        let cpi_ctx = CpiContext::new(token_program, cpi_accounts);
+        transfer_checked(cpi_ctx, amount, decimals)
+    }
+
+    pub fn withdraw_token(
+        &mut self,
+        x: bool,
+        amount: u64
+    ) -> Result<()> {
+        let (from, to, mint, decimals, token_program) = match x {
+            true => (self.vault_x.to_account_info(), self.user_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.user_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info())
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.auth.to_account_info()
+        };
+
+        let pool_key = self.pool.key();
+        let seeds = &[
+            &b"auth"[..],
+            pool_key.as_ref(),
+            &[self.pool.auth_bump]
+        ];
+
+        let signer_seeds = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+        transfer_checked(cpi_ctx, amount, decimals)
+    }
+}
@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AMMErrorCode,
+    state::{ClPool, Tick},
+    tick_math::{MAX_TICK, MIN_TICK},
+};
+
+// creates a tick and links it into the pool's sorted tick list between its two neighbours
+#[derive(Accounts)]
+#[instruction(index: i32)]
+pub struct InitTick<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds=[
+            b"cl_pool",
+            pool.mint_x.as_ref(),
+            pool.mint_y.as_ref(),
+            pool.fee_tier.as_ref(),
+            pool.tick_spacing.to_le_bytes().as_ref()
+        ],
+        bump=pool.pool_bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    #[account(
+        init,
+        payer=payer,
+        seeds=[b"tick", pool.key().as_ref(), index.to_le_bytes().as_ref()],
+        bump,
+        space= 8 + Tick::INIT_SPACE
+    )]
+    pub tick: Box<Account<'info, Tick>>,
+
+    // closest existing ticks below and above `index`, None at the ends of the list
+    #[account(mut, constraint = prev_tick.pool == pool.key() @ AMMErrorCode::InvalidTickNeighbors)]
+    pub prev_tick: Option<Box<Account<'info, Tick>>>,
+    #[account(mut, constraint = next_tick.pool == pool.key() @ AMMErrorCode::InvalidTickNeighbors)]
+    pub next_tick: Option<Box<Account<'info, Tick>>>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> InitTick<'info> {
+    pub fn init_tick(&mut self, bumps: InitTickBumps, index: i32) -> Result<()> {
+        require!(
+            (MIN_TICK..=MAX_TICK).contains(&index) && index % self.pool.tick_spacing as i32 == 0,
+            AMMErrorCode::InvalidTick
+        );
+
+        let prev = self.prev_tick.as_ref().map(|t| t.index);
+        let next = self.next_tick.as_ref().map(|t| t.index);
+
+        // the neighbours have to be adjacent in the list with `index` falling between them
+        if let Some(prev_tick) = &self.prev_tick {
+            require!(prev_tick.index < index && prev_tick.next == next, AMMErrorCode::InvalidTickNeighbors);
+        }
+        if let Some(next_tick) = &self.next_tick {
+            require!(next_tick.index > index && next_tick.prev == prev, AMMErrorCode::InvalidTickNeighbors);
+        }
+        // with no neighbours at all the list has to be empty
+        if prev.is_none() && next.is_none() {
+            require!(
+                self.pool.tick_below.is_none() && self.pool.tick_above.is_none(),
+                AMMErrorCode::InvalidTickNeighbors
+            );
+        }
+
+        if let Some(prev_tick) = &mut self.prev_tick {
+            prev_tick.next = Some(index);
+        }
+        if let Some(next_tick) = &mut self.next_tick {
+            next_tick.prev = Some(index);
+        }
+
+        // everything below the current tick counts as already earned outside
+        let below = index <= self.pool.tick_current;
+        let (outside_x, outside_y) = match below {
+            true => (self.pool.fee_growth_x, self.pool.fee_growth_y),
+            false => (0, 0)
+        };
+
+        match below {
+            true => if self.pool.tick_below.is_none_or(|t| index > t) {
+                self.pool.tick_below = Some(index);
+            },
+            false => if self.pool.tick_above.is_none_or(|t| index < t) {
+                self.pool.tick_above = Some(index);
+            }
+        }
+
+        self.tick.set_inner(Tick {
+            pool: self.pool.key(),
+            index,
+            prev,
+            next,
+            liquidity_gross: 0,
+            liquidity_net: 0,
+            fee_growth_outside_x: outside_x,
+            fee_growth_outside_y: outside_y,
+            bump: bumps.tick
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AMMErrorCode, events::{ClPoolLocked, ClPoolUnlocked}, state::{ClPool, FeeTier}};
+
+// concentrated liquidity pools have no authority of their own, the admin of their fee tier stands in
+#[derive(Accounts)]
+pub struct ClUpdate<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one=admin @ AMMErrorCode::InvalidAuth,
+        seeds=[b"fee_tier", fee_tier.fee.to_le_bytes().as_ref()],
+        bump=fee_tier.bump
+    )]
+    pub fee_tier: Account<'info, FeeTier>,
+
+    #[account(
+        mut,
+        has_one=fee_tier,
+        seeds=[
+            b"cl_pool",
+            pool.mint_x.as_ref(),
+            pool.mint_y.as_ref(),
+            pool.fee_tier.as_ref(),
+            pool.tick_spacing.to_le_bytes().as_ref()
+        ],
+        bump=pool.pool_bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+}
+
+impl<'info> ClUpdate<'info> {
+    pub fn cl_lock(&mut self) -> Result<()> {
+        self.pool.locked = true;
+
+        emit!(ClPoolLocked {
+            pool: self.pool.key(),
+            admin: self.admin.key(),
+        });
+        Ok(())
+    }
+
+    pub fn cl_unlock(&mut self) -> Result<()> {
+        self.pool.locked = false;
+
+        emit!(ClPoolUnlocked {
+            pool: self.pool.key(),
+            admin: self.admin.key(),
+        });
+        Ok(())
+    }
+}
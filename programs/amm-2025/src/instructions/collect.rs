@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{error::AMMErrorCode, events::ProtocolFeesCollected, state::Config};
+
+// permissionless, the accrued fees can only ever go to the configured treasury
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint=mint_x,
+        token::authority=config.treasury,
+        token::token_program=token_program_x,
+    )]
+    pub treasury_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=mint_y,
+        token::authority=config.treasury,
+        token::token_program=token_program_y,
+    )]
+    pub treasury_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: this is safe
+    #[account(seeds=[b"auth", config.key().as_ref()], bump=config.auth_bump)]
+    pub auth: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[
+            b"config",
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee_tier.as_ref(),
+            &[config.curve_type as u8]
+        ],
+        bump=config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CollectProtocolFees<'info> {
+    pub fn collect_protocol_fees(&mut self) -> Result<()> {
+        require_keys_neq!(self.config.treasury, Pubkey::default(), AMMErrorCode::NoTreasurySet);
+
+        let (amount_x, amount_y) = (self.config.protocol_fees_x, self.config.protocol_fees_y);
+
+        if amount_x > 0 {
+            self.withdraw_fees(true, amount_x)?;
+        }
+        if amount_y > 0 {
+            self.withdraw_fees(false, amount_y)?;
+        }
+
+        self.config.protocol_fees_x = 0;
+        self.config.protocol_fees_y = 0;
+
+        emit!(ProtocolFeesCollected {
+            config: self.config.key(),
+            treasury: self.config.treasury,
+            amount_x,
+            amount_y,
+        });
+        Ok(())
+    }
+
+    pub fn withdraw_fees(
+        &self,
+        x: bool,
+        amount: u64
+    ) -> Result<()> {
+        let (from, to, mint, decimals, token_program) = match x {
+            true => (self.vault_x.to_account_info(), self.treasury_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.treasury_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info()),
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            mint,
+            to,
+            authority: self.auth.to_account_info(),
+        };
+
+        let config_key = self.config.key();
+        let seeds = &[
+            &b"auth"[..],
+            config_key.as_ref(),
+            &[self.config.auth_bump]
+        ];
+
+        let signer_seeds = &[&seeds[..]];
+        let ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+        transfer_checked(ctx, amount, decimals)
+    }
+}
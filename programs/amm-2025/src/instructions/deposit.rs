@@ -2,79 +2,106 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Token, Mint, MintTo, TokenAccount, mint_to},
-    token_interface::{transfer_checked, TransferChecked}
+    token_interface::{mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked}
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::{error::AMMErrorCode, state::config::Config};
+use crate::{constants::MINIMUM_LIQUIDITY, curves::{first_deposit_lp, swap_exact_in}, error::AMMErrorCode, events::{LiquidityAdded, Swapped}, state::config::Config, token::{amount_after_fee, amount_before_fee, native_side, unwrap_sol, wrap_sol}};
 use crate::{assert_not_locked, assert_not_expired, assert_non_zero};
 
 #[derive(Accounts)]
 pub struct  Deposit<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    pub mint_x: Box<Account<'info, Mint>>,
-    pub mint_y: Box<Account<'info, Mint>>,
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut, 
         seeds=[b"liquidity", config.key().as_ref()],
-        bump=config.lp_bump
+        bump=config.lp_bump,
+        mint::token_program=token_program
     )]
-    pub mint_lp: Box<Account<'info, Mint>>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint=config.mint_x,
         associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
     )]
-    pub vault_x:Box<Account<'info, TokenAccount>>,
+    pub vault_x:Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut, 
         associated_token::mint= config.mint_y,
-        associated_token::authority=auth
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
     )]
-    pub vault_y: Box<Account<'info, TokenAccount>>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
-        associated_token::mint = config.mint_x,
-        associated_token::authority = user 
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_x,
     )]
-    pub user_x: Box<Account<'info, TokenAccount>>,
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
-        associated_token::mint= config.mint_y,
-        associated_token::authority= user
+        init_if_needed,
+        payer = user,
+        associated_token::mint= mint_y,
+        associated_token::authority= user,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer= user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub user_y: Box<Account<'info, TokenAccount>>,
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    // holds the locked minimum liquidity, nothing ever moves LP out of it
     #[account(
         init_if_needed,
         payer= user,
         associated_token::mint = mint_lp,
-        associated_token::authority = user
+        associated_token::authority = auth,
+        associated_token::token_program = token_program,
     )]
-    pub user_lp: Box<Account<'info, TokenAccount>>,
+    pub vault_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: this is safe
-    #[account(seeds=[b"auth"], bump=config.auth_bump)]
+    #[account(seeds=[b"auth", config.key().as_ref()], bump=config.auth_bump)]
     pub auth: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         has_one=mint_x,
         has_one=mint_y,
         seeds = [
             b"config",
-            config.seed.to_le_bytes().as_ref()
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee_tier.as_ref(),
+            &[config.curve_type as u8]
         ],
         bump=config.config_bump,
     )]
     pub config: Account<'info, Config>,
 
-    pub token_program: Program<'info, Token>,
+    /// CHECK: price feed of oracle pools, matched against config.oracle when it is read
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
@@ -86,35 +113,179 @@ impl<'info> Deposit<'info> {
         max_x: u64,
         max_y: u64,
         expiration: i64,
+        native: bool,
     ) -> Result<()> {
         assert_not_locked!(self.config.locked);
         assert_not_expired!(expiration);
         assert_non_zero!([amount, max_x, max_y]);
 
-        let (x,y) = match self.mint_lp.supply == 0 && self.vault_x.amount == 0 && self.vault_y.amount == 0 {
-            true => (max_x, max_y),
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // on the first deposit `amount` is the minimum LP the user accepts.
+        // x and y are what reaches the vaults, transfer fees come on top
+        let (x, y, lp) = match self.mint_lp.supply == 0 {
+            true => {
+                let x = amount_after_fee(&self.mint_x, max_x)?;
+                let y = amount_after_fee(&self.mint_y, max_y)?;
+                let lp = first_deposit_lp(reserve_x, reserve_y, x, y, amount)?;
+                (x, y, lp)
+            },
             false => {
-                let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                    self.vault_x.amount,
-                    self.vault_y.amount,
-                    self.mint_lp.supply,
-                    amount,
-                    6
-                ).map_err(AMMErrorCode::from)?;
-                (amounts.x, amounts.y)
+                let (x, y) = self.config
+                    .curve(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?
+                    .deposit_amounts(self.mint_lp.supply, amount)?;
+                (x, y, amount)
             }
         };
-        
-        self.deposit_tokens(true, max_x)?;
-        self.deposit_tokens(false, max_y)?;
-        self.mint_lp_token(amount)
+
+        let (gross_x, gross_y) = (amount_before_fee(&self.mint_x, x)?, amount_before_fee(&self.mint_y, y)?);
+
+        // Check for slippage
+        require!(gross_x <= max_x && gross_y <= max_y, AMMErrorCode::SlippageExceeded);
+
+        let sol = self.native_side(native)?;
+        if let Some(sol_x) = sol {
+            let amount = match sol_x {
+                true => gross_x,
+                false => gross_y
+            };
+            self.wrap_sol(sol_x, amount)?;
+        }
+
+        self.deposit_tokens(true, gross_x)?;
+        self.deposit_tokens(false, gross_y)?;
+        if let Some(sol_x) = sol {
+            self.unwrap_sol(sol_x)?;
+        }
+        if self.mint_lp.supply == 0 {
+            self.mint_lp_token(false, MINIMUM_LIQUIDITY)?;
+        }
+        self.mint_lp_token(true, lp)?;
+
+        emit!(LiquidityAdded {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x: x,
+            amount_y: y,
+            amount_lp: lp,
+            reserve_x: reserve_x.checked_add(x).ok_or(AMMErrorCode::Overflow)?,
+            reserve_y: reserve_y.checked_add(y).ok_or(AMMErrorCode::Overflow)?,
+        });
+        Ok(())
     }
 
 
+    // swaps the share of `amount_in` that balances the pool ratio and deposits both sides,
+    // the swapped side never leaves the vault and any rounding dust is sent back
+    pub fn deposit_single(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        min_lp: u64,
+        expiration: i64,
+        native: bool,
+    ) -> Result<()> {
+        assert_not_locked!(self.config.locked);
+        assert_not_expired!(expiration);
+        assert_non_zero!([amount_in]);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.config.apply_pending_fee(now);
+        let fee = self.config.swap_fee(now);
+        self.update_oracle_price(now)?;
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let supply = self.mint_lp.supply;
+        assert_non_zero!([reserve_x, reserve_y, supply]);
+
+        let (reserve_in, reserve_out, mint_in) = match is_x {
+            true => (reserve_x, reserve_y, &self.mint_x),
+            false => (reserve_y, reserve_x, &self.mint_y)
+        };
+        // the zap works on what reaches the vault
+        let received = amount_after_fee(mint_in, amount_in)?;
+
+        let curve = self.config.curve(reserve_x, reserve_y, now)?;
+
+        let swap_in = curve.zap_amount(is_x, received, fee)?;
+        let res = swap_exact_in(&*curve, is_x, swap_in, fee)?;
+        assert_non_zero!([res.amount_in, res.amount_out]);
+        let protocol_fee = self.config.accrue_protocol_fee(is_x, res.fee)?;
+
+        // pool after the internal swap, the protocol share of the fee is not LP liquidity
+        let reserve_in_after = reserve_in
+            .checked_add(res.amount_in.checked_sub(protocol_fee).ok_or(AMMErrorCode::Underflow)?)
+            .ok_or(AMMErrorCode::Overflow)?;
+        let reserve_out_after = reserve_out
+            .checked_sub(res.amount_out)
+            .ok_or(AMMErrorCode::Underflow)?;
+        let rest_in = received.checked_sub(res.amount_in).ok_or(AMMErrorCode::Underflow)?;
+        let rest_out = res.amount_out;
+
+        // LP both leftovers can pay for, then exactly what that LP costs rounded up
+        let lp = ((rest_in as u128 * supply as u128 / reserve_in_after as u128)
+            .min(rest_out as u128 * supply as u128 / reserve_out_after as u128)) as u64;
+        require!(lp >= min_lp, AMMErrorCode::SlippageExceeded);
+        assert_non_zero!([lp]);
+
+        let deposit_in = (lp as u128 * reserve_in_after as u128).div_ceil(supply as u128) as u64;
+        let deposit_out = (lp as u128 * reserve_out_after as u128).div_ceil(supply as u128) as u64;
+
+        let gross_in = amount_before_fee(mint_in, res.amount_in.checked_add(deposit_in).ok_or(AMMErrorCode::Overflow)?)?;
+        let sol = self.native_side(native)?;
+        if sol == Some(is_x) {
+            self.wrap_sol(is_x, gross_in)?;
+        }
+
+        self.deposit_tokens(is_x, gross_in)?;
+        if rest_out > deposit_out {
+            self.refund_tokens(!is_x, rest_out - deposit_out)?;
+        }
+        if let Some(sol_x) = sol {
+            self.unwrap_sol(sol_x)?;
+        }
+        self.mint_lp_token(true, lp)?;
+
+        let deposited_in = reserve_in_after.checked_add(deposit_in).ok_or(AMMErrorCode::Overflow)?;
+        let deposited_out = reserve_out_after.checked_add(deposit_out).ok_or(AMMErrorCode::Overflow)?;
+        let (after_swap, after_deposit) = match is_x {
+            true => ((reserve_in_after, reserve_out_after), (deposited_in, deposited_out)),
+            false => ((reserve_out_after, reserve_in_after), (deposited_out, deposited_in))
+        };
+        self.config.update_volatility(now, (reserve_x, reserve_y), after_swap)?;
+
+        emit!(Swapped {
+            config: self.config.key(),
+            user: self.user.key(),
+            x: is_x,
+            amount_in: res.amount_in,
+            amount_out: res.amount_out,
+            fee: res.fee,
+            protocol_fee,
+            reserve_x: after_swap.0,
+            reserve_y: after_swap.1,
+        });
+
+        let (amount_x, amount_y) = match is_x {
+            true => (deposit_in, deposit_out),
+            false => (deposit_out, deposit_in)
+        };
+        emit!(LiquidityAdded {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            amount_lp: lp,
+            reserve_x: after_deposit.0,
+            reserve_y: after_deposit.1,
+        });
+        Ok(())
+    }
+
     pub fn deposit_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
-        let (from, to, mint, decimals) = match is_x{
-            true => (self.user_x.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals),
-            false => (self.user_y.to_account_info(), self.vault_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals),
+        let (from, to, mint, decimals, token_program) = match is_x{
+            true => (self.user_x.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.vault_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info()),
         };
 
         let cpi_accounts = TransferChecked {
@@ -124,22 +295,55 @@ impl<'info> Deposit<'info> {
             authority: self.user.to_account_info(),
         };
 
-        let ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        let ctx = CpiContext::new(token_program, cpi_accounts);
         transfer_checked(ctx,amount, decimals)
     }
 
+    pub fn refund_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, to, mint, decimals, token_program) = match is_x {
+            true => (self.vault_x.to_account_info(), self.user_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.user_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info()),
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            mint,
+            to,
+            authority: self.auth.to_account_info(),
+        };
+
+        let config_key = self.config.key();
+        let seeds = &[
+            &b"auth"[..],
+            config_key.as_ref(),
+            &[self.config.auth_bump]
+        ];
+
+        let signer_seeds = &[&seeds[..]];
+        let ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+        transfer_checked(ctx, amount, decimals)
+    }
+
     pub fn mint_lp_token(
         &self,
+        to_user: bool,
         amount:u64
     ) -> Result<()> {
+        let to = match to_user {
+            true => self.user_lp.to_account_info(),
+            false => self.vault_lp.to_account_info(),
+        };
+
         let accounts = MintTo {
             mint: self.mint_lp.to_account_info(),
-            to: self.user_lp.to_account_info(),
-            authority: self.config.to_account_info(),
+            to,
+            authority: self.auth.to_account_info(),
         };
 
+        let config_key = self.config.key();
         let seeds = &[
             &b"auth"[..],
+            config_key.as_ref(),
             &[self.config.auth_bump]
         ];
 
@@ -148,5 +352,35 @@ impl<'info> Deposit<'info> {
         
         mint_to(ctx, amount)
     }
-}
 
+    // oracle pools price off the feed, a stale one fails the instruction
+    pub fn update_oracle_price(&mut self, now: i64) -> Result<()> {
+        let feed = self.oracle.as_ref().map(|o| o.to_account_info());
+        self.config.update_oracle_price(feed.as_ref(), self.mint_x.decimals, self.mint_y.decimals, now)
+    }
+
+    // with `native` the user's WSOL account is funded from lamports for the input
+    // and closed afterwards, so any SOL leg settles in native SOL
+    pub fn native_side(&self, native: bool) -> Result<Option<bool>> {
+        match native {
+            true => Ok(Some(native_side(&self.mint_x.key(), &self.mint_y.key())?)),
+            false => Ok(None)
+        }
+    }
+
+    pub fn wrap_sol(&self, is_x: bool, amount: u64) -> Result<()> {
+        let (account, token_program) = match is_x {
+            true => (self.user_x.to_account_info(), self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.token_program_y.to_account_info())
+        };
+        wrap_sol(self.user.to_account_info(), account, self.system_program.to_account_info(), token_program, amount)
+    }
+
+    pub fn unwrap_sol(&self, is_x: bool) -> Result<()> {
+        let (account, token_program) = match is_x {
+            true => (self.user_x.to_account_info(), self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.token_program_y.to_account_info())
+        };
+        unwrap_sol(self.user.to_account_info(), account, token_program)
+    }
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AMMErrorCode, events::FeeTierCreated, program::Amm2025, state::FeeTier};
+
+// tiers are managed by the program upgrade authority
+#[derive(Accounts)]
+#[instruction(fee:u16)]
+pub struct CreateFeeTier<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer=admin,
+        seeds=[b"fee_tier", fee.to_le_bytes().as_ref()],
+        bump,
+        space= 8 + FeeTier::INIT_SPACE
+    )]
+    pub fee_tier: Account<'info, FeeTier>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm2025>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AMMErrorCode::InvalidAuth)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> CreateFeeTier<'info> {
+    pub fn create_fee_tier(&mut self, bumps: CreateFeeTierBumps, fee: u16) -> Result<()> {
+        // swaps and zaps reject a fee of 100%, a pool on such a tier could never trade
+        require!(fee < 10000, AMMErrorCode::InvalidFee);
+
+        self.fee_tier.set_inner(FeeTier {
+            fee,
+            admin: self.admin.key(),
+            bump: bumps.fee_tier
+        });
+
+        emit!(FeeTierCreated {
+            fee_tier: self.fee_tier.key(),
+            admin: self.admin.key(),
+            fee,
+        });
+        Ok(())
+    }
+}
@@ -1,56 +1,109 @@
 
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token, TokenAccount}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
 
-use crate::state::config::Config;
+use crate::constants::{MAX_AMP, MIN_AMP, MIN_WEIGHT};
+use crate::curves::CurveType;
+use crate::state::{config::Config, FeeTier};
 use crate::error::AMMErrorCode;
+use crate::events::PoolInitialized;
+use crate::token::assert_supported_mint;
+
+// curve settings chosen at initialize, a curve ignores the fields it does not use
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct InitParams {
+    pub curve_type: CurveType,
+    // StableSwap amplification
+    pub amp: u64,
+    // Weighted share of x, in basis points
+    pub weight_x: u16,
+    pub oracle: OracleParams,
+    // creates the pool without an authority, immutable from the start
+    pub renounce_authority: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct OracleParams {
+    pub max_staleness: i64,
+    pub max_confidence: u16,
+    pub spread: u16,
+}
 
 #[derive(Accounts)]
-#[instruction(seed:u64)]
+#[instruction(params: InitParams)]
 pub struct Initialize<'info>{
     #[account(mut)]
     pub initializer: Signer<'info>,
-    pub mint_x: Account<'info, Mint>,
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    // checked here so a bad pair fails before any vault is created
+    #[account(
+        mint::token_program=token_program_y,
+        constraint = mint_y.key() != mint_x.key() @ AMMErrorCode::IdenticalMints,
+        constraint = mint_x.key() < mint_y.key() @ AMMErrorCode::InvalidMintOrder
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds=[b"fee_tier", fee_tier.fee.to_le_bytes().as_ref()],
+        bump=fee_tier.bump
+    )]
+    pub fee_tier: Account<'info, FeeTier>,
+
     #[account(
         init,
         seeds = [b"liquidity",config.key.as_ref()],
         payer=initializer,
         bump,
         mint::decimals=6,
-        mint::authority=auth
+        mint::authority=auth,
+        mint::token_program=token_program
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init,
         payer = initializer,
         associated_token::mint=mint_x,
         associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer = initializer,
         associated_token::mint=mint_y,
         associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECKED: this is safer
-    #[account(seeds=[b"auth"], bump)]
+    #[account(seeds=[b"auth", config.key().as_ref()], bump)]
     pub auth: UncheckedAccount<'info>,
 
     #[account(
         init,
         payer=initializer,
-        seeds=[b"config", seed.to_le_bytes().as_ref()],
+        seeds=[
+            b"config",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+            fee_tier.key().as_ref(),
+            &[params.curve_type as u8]
+        ],
         bump,
         space= 8 + Config::INIT_SPACE
     )]
     pub config: Account<'info, Config>,
 
-    pub token_program: Program<'info, Token>,
+    /// CHECK: price feed of oracle pools, read and pinned in config.oracle
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    // x and y can each live under Token or Token-2022, LP is minted by `token_program`
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>
 }
@@ -59,27 +112,82 @@ impl<'info> Initialize<'info> {
     pub fn init (
         &mut self,
         bumps: InitializeBumps,
-        seeds: u64,
-        fee: u16,
-        authority: Pubkey,
+        params: InitParams,
     ) -> Result<()> {
+        assert_supported_mint(&self.mint_x)?;
+        assert_supported_mint(&self.mint_y)?;
 
-        require!(fee <= 10000, AMMErrorCode::InvalidFee);
-        
-        let(auth_bump, config_bump, lp_bump) = (
-            &bumps.auth, &bumps.config, &bumps.mint_lp
+        let curve_type = params.curve_type;
+        // only StableSwap reads A, it prices raw amounts so both sides need the same decimals
+        let amp = match curve_type {
+            CurveType::StableSwap => {
+                require!((MIN_AMP..=MAX_AMP).contains(&params.amp), AMMErrorCode::InvalidAmp);
+                require!(self.mint_x.decimals == self.mint_y.decimals, AMMErrorCode::DecimalsMismatch);
+                params.amp
+            },
+            _ => 0
+        };
+        let weight_x = match curve_type {
+            CurveType::Weighted => {
+                require!(
+                    (MIN_WEIGHT..=10000 - MIN_WEIGHT).contains(&params.weight_x),
+                    AMMErrorCode::InvalidWeights
+                );
+                params.weight_x
+            },
+            _ => 5000
+        };
+        // oracle pools lock on a stale feed and need an authority to unlock
+        require!(
+            !(params.renounce_authority && curve_type == CurveType::Oracle),
+            AMMErrorCode::InvalidCurveType
         );
+        let oracle = match curve_type {
+            CurveType::Oracle => {
+                let OracleParams { max_staleness, max_confidence, spread } = params.oracle;
+                require!(
+                    max_staleness > 0 && max_confidence <= 10000 && spread < 10000,
+                    AMMErrorCode::InvalidOracleParams
+                );
+                params.oracle
+            },
+            _ => OracleParams::default()
+        };
 
         self.config.init(
-            seeds,
-            authority,
             self.mint_x.key(),
             self.mint_y.key(),
-            fee,
-            *auth_bump,
-            *config_bump,
-            *lp_bump
+            &self.fee_tier,
+            &InitParams { curve_type, amp, weight_x, oracle, ..params },
+            &bumps
         );
+
+        // a stale feed fails trades until refresh_oracle locks the pool, only the authority can lift that
+        if curve_type == CurveType::Oracle {
+            let feed = self.oracle.as_ref().ok_or(AMMErrorCode::InvalidOracle)?.to_account_info();
+
+            self.config.oracle = feed.key();
+            self.config.oracle_price = self.config
+                .read_oracle(Some(&feed), self.mint_x.decimals, self.mint_y.decimals, Clock::get()?.unix_timestamp)?
+                .ok_or(AMMErrorCode::StaleOracle)?;
+        }
+
+        emit!(PoolInitialized {
+            config: self.config.key(),
+            initializer: self.initializer.key(),
+            authority: self.config.authority,
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            mint_lp: self.mint_lp.key(),
+            fee_tier: self.fee_tier.key(),
+            curve_type,
+            amp,
+            weight_x,
+            weight_y: 10000 - weight_x,
+            oracle: self.config.oracle,
+            spread: self.config.spread,
+            fee: self.config.fee,
+        });
         Ok(())
     }
 }
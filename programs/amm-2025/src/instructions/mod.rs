@@ -8,4 +8,24 @@ pub mod swap;
 pub use swap::*;
 pub mod update;
 pub use update::*;
+pub mod quote;
+pub use quote::*;
+pub mod collect;
+pub use collect::*;
+pub mod fee_tier;
+pub use fee_tier::*;
+pub mod refresh_oracle;
+pub use refresh_oracle::*;
 
+pub mod cl_pool;
+pub use cl_pool::*;
+pub mod cl_tick;
+pub use cl_tick::*;
+pub mod cl_position;
+pub use cl_position::*;
+pub mod cl_liquidity;
+pub use cl_liquidity::*;
+pub mod cl_swap;
+pub use cl_swap::*;
+pub mod cl_update;
+pub use cl_update::*;
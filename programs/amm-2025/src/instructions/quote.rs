@@ -0,0 +1,182 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::{assert_non_zero, assert_not_expired, assert_not_locked, curves::{first_deposit_lp, swap_exact_in, CurveType}, error::AMMErrorCode, math::price_impact, state::Config, token::{amount_after_fee, amount_before_fee}};
+
+// read-only view over the live pool, results go back to the client as return data
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds=[b"liquidity", config.key().as_ref()],
+        bump=config.lp_bump
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        associated_token::mint=mint_x,
+        associated_token::authority=auth,
+        associated_token::token_program=mint_x.to_account_info().owner,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        associated_token::mint=mint_y,
+        associated_token::authority=auth,
+        associated_token::token_program=mint_y.to_account_info().owner,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: this is safe
+    #[account(seeds=[b"auth", config.key().as_ref()], bump=config.auth_bump)]
+    pub auth: UncheckedAccount<'info>,
+
+    #[account(
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds = [
+            b"config",
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee_tier.as_ref(),
+            &[config.curve_type as u8]
+        ],
+        bump=config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: price feed of oracle pools, matched against config.oracle when it is read
+    pub oracle: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    // basis points below the spot price
+    pub price_impact: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LiquidityQuote {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub amount_lp: u64,
+}
+
+impl<'info> Quote<'info> {
+    pub fn quote_swap(
+        &self,
+        x: bool,
+        amount: u64,
+        min: u64,
+        expiration: i64
+    ) -> Result<SwapQuote> {
+        assert_not_locked!(self.config.locked);
+        assert_not_expired!(expiration);
+        assert_non_zero!([amount]);
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let fee = self.config.swap_fee(now);
+
+        // oracle pools quote off the live feed, a quote can not lock the pool so a stale feed fails
+        let mut config = (*self.config).clone();
+        if config.curve_type == CurveType::Oracle {
+            let feed = self.oracle.as_ref().map(|o| o.to_account_info());
+            config.oracle_price = config
+                .read_oracle(feed.as_ref(), self.mint_x.decimals, self.mint_y.decimals, now)?
+                .ok_or(AMMErrorCode::StaleOracle)?;
+        }
+        let curve = config.curve(reserve_x, reserve_y, now)?;
+
+        let (mint_in, mint_out) = match x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x)
+        };
+
+        // same accounting as the swap: the curve sees what reaches the vault, `min` what reaches the user
+        let res = swap_exact_in(&*curve, x, amount_after_fee(mint_in, amount)?, fee)?;
+        let amount_out = amount_after_fee(mint_out, res.amount_out)?;
+        require!(amount_out >= min, AMMErrorCode::SlippageExceeded);
+
+        Ok(SwapQuote {
+            amount_in: amount,
+            amount_out,
+            fee: res.fee,
+            price_impact: price_impact(
+                curve.spot_price()?,
+                x,
+                res.amount_in - res.fee,
+                res.amount_out
+            )?,
+        })
+    }
+
+    pub fn quote_deposit(
+        &self,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64
+    ) -> Result<LiquidityQuote> {
+        assert_not_locked!(self.config.locked);
+        assert_not_expired!(expiration);
+        assert_non_zero!([amount, max_x, max_y]);
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        let (x, y, lp) = match self.mint_lp.supply == 0 {
+            true => {
+                let x = amount_after_fee(&self.mint_x, max_x)?;
+                let y = amount_after_fee(&self.mint_y, max_y)?;
+                let lp = first_deposit_lp(reserve_x, reserve_y, x, y, amount)?;
+                (x, y, lp)
+            },
+            false => {
+                let (x, y) = self.config
+                    .curve(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?
+                    .deposit_amounts(self.mint_lp.supply, amount)?;
+                (x, y, amount)
+            }
+        };
+
+        // quoted amounts are what the user sends, transfer fees included
+        let (x, y) = (amount_before_fee(&self.mint_x, x)?, amount_before_fee(&self.mint_y, y)?);
+
+        // Check for slippage
+        require!(x <= max_x && y <= max_y, AMMErrorCode::SlippageExceeded);
+
+        Ok(LiquidityQuote { amount_x: x, amount_y: y, amount_lp: lp })
+    }
+
+    pub fn quote_withdraw(
+        &self,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64
+    ) -> Result<LiquidityQuote> {
+        assert_not_locked!(self.config.locked);
+        assert_not_expired!(expiration);
+        assert_non_zero!([amount]);
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        let (x, y) = self.config
+            .curve(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?
+            .withdraw_amounts(self.mint_lp.supply, amount)?;
+
+        // quoted amounts are what the user receives after transfer fees
+        let (x, y) = (amount_after_fee(&self.mint_x, x)?, amount_after_fee(&self.mint_y, y)?);
+
+        // Check for slippage
+        require!(min_x <= x && min_y <= y, AMMErrorCode::SlippageExceeded);
+
+        Ok(LiquidityQuote { amount_x: x, amount_y: y, amount_lp: amount })
+    }
+}
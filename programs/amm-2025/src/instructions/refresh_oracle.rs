@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{curves::CurveType, error::AMMErrorCode, events::OracleStale, state::Config};
+
+// trades fail on a stale feed without leaving a trace, this is where the pool gets locked for it
+#[derive(Accounts)]
+pub struct RefreshOracle<'info> {
+    pub user: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        has_one=oracle @ AMMErrorCode::InvalidOracle,
+        seeds=[
+            b"config",
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee_tier.as_ref(),
+            &[config.curve_type as u8]
+        ],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: the feed pinned in config.oracle, parsed by PriceFeed::load
+    pub oracle: UncheckedAccount<'info>,
+}
+
+impl<'info> RefreshOracle<'info> {
+    // a fresh feed only updates the stored price, unlocking stays with the authority
+    pub fn refresh_oracle(&mut self) -> Result<()> {
+        require!(self.config.curve_type == CurveType::Oracle, AMMErrorCode::InvalidCurveType);
+
+        let now = Clock::get()?.unix_timestamp;
+        let feed = self.oracle.to_account_info();
+        match self.config.read_oracle(Some(&feed), self.mint_x.decimals, self.mint_y.decimals, now)? {
+            Some(price) => self.config.oracle_price = price,
+            None => {
+                self.config.locked = true;
+                emit!(OracleStale {
+                    config: self.config.key(),
+                    oracle: self.config.oracle,
+                    user: self.user.key(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,66 +1,77 @@
-use std::result;
-
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked}};
-use constant_product_curve::{ConstantProduct, LiquidityPair};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked}};
 
-use crate::{assert_non_zero, assert_not_expired, assert_not_locked, error::AMMErrorCode, state::Config};
+use crate::{assert_non_zero, assert_not_expired, assert_not_locked, curves::{swap_exact_in, swap_exact_out}, error::AMMErrorCode, events::Swapped, state::Config, token::{amount_after_fee, amount_before_fee, native_side, unwrap_sol, wrap_sol}};
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    pub mint_x: Box<Account<'info, Mint>>,
-    pub mint_y: Box<Account<'info, Mint>>,
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init_if_needed,
         payer=user,
         associated_token::mint=mint_x,
-        associated_token::authority=auth,
+        associated_token::authority=user,
+        associated_token::token_program=token_program_x,
     )]
-    pub user_x: Box<Account<'info, TokenAccount>>,
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer=user,
         associated_token::mint=mint_y,
-        associated_token::authority=auth,
+        associated_token::authority=user,
+        associated_token::token_program=token_program_y,
     )]
-    pub user_y: Box<Account<'info, TokenAccount>>,
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint=mint_x,
         associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
     )]
-    pub vault_x: Box<Account<'info, TokenAccount>>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         associated_token::mint=mint_y,
         associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
     )]
-    pub vault_y: Box<Account<'info, TokenAccount>>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECKED: this is the safe account only for the sign the transaction - actually more secure
     #[account(
-        seeds=[b"auth"],
+        seeds=[b"auth", config.key().as_ref()],
         bump=config.auth_bump
     )]
     pub auth: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         has_one=mint_x,
         has_one=mint_y,
         seeds=[
             b"config",
-            config.seed.to_le_bytes().as_ref()
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee_tier.as_ref(),
+            &[config.curve_type as u8]
         ],
         bump= config.config_bump
     )]
     pub config: Account<'info, Config>,
-    
-    pub token_program: Program<'info, Token>,
+
+    /// CHECK: price feed of oracle pools, matched against config.oracle when it is read
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 
@@ -72,31 +83,134 @@ impl<'info> Swap<'info> {
         x: bool,
         amount: u64,
         min: u64,
-        expiration: i64
+        expiration: i64,
+        native: bool
     ) -> Result<()> {
         assert_not_locked!(self.config.locked);
         assert_not_expired!(expiration);
         assert_non_zero!([amount]);
 
-        let mut curve = ConstantProduct::init(
-            self.vault_x.amount,
-            self.vault_y.amount,
-            self.vault_x.amount,
-            self.config.fee,
-            None
-        ).map_err(AMMErrorCode::from)?;
-
-        let p = match x {
-            true => LiquidityPair::X,
-            false => LiquidityPair::Y
+        let now = Clock::get()?.unix_timestamp;
+        self.config.apply_pending_fee(now);
+        let fee = self.config.swap_fee(now);
+        self.update_oracle_price(now)?;
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let curve = self.config.curve(reserve_x, reserve_y, now)?;
+
+        let (mint_in, mint_out) = match x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x)
         };
 
-        let res = curve.swap(p, amount, min).map_err(AMMErrorCode::from)?;
+        // the curve prices what reaches the vault, `min` is checked on what reaches the user
+        let received = amount_after_fee(mint_in, amount)?;
+        let res = swap_exact_in(&*curve, x, received, fee)?;
+
+        assert_non_zero!([res.amount_out]);
+        require!(amount_after_fee(mint_out, res.amount_out)? >= min, AMMErrorCode::SlippageExceeded);
 
-        assert_non_zero!([res.deposit, res.withdraw]);
+        let sol = self.native_side(native)?;
+        if sol == Some(x) {
+            self.wrap_sol(x, amount)?;
+        }
 
+        // input goes into the matching vault, output leaves the opposite one
         self.deposit_token(x, amount)?;
-        self.withdraw_token(x, amount)?;
+        self.withdraw_token(!x, res.amount_out)?;
+
+        if let Some(sol_x) = sol {
+            self.unwrap_sol(sol_x)?;
+        }
+
+        self.settle_swap(x, res.amount_in, res.amount_out, res.fee, now)
+    }
+
+    pub fn swap_exact_out(
+        &mut self,
+        x: bool,
+        amount_out: u64,
+        max_in: u64,
+        expiration: i64,
+        native: bool
+    ) -> Result<()> {
+        assert_not_locked!(self.config.locked);
+        assert_not_expired!(expiration);
+        assert_non_zero!([amount_out]);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.config.apply_pending_fee(now);
+        let fee = self.config.swap_fee(now);
+        self.update_oracle_price(now)?;
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let curve = self.config.curve(reserve_x, reserve_y, now)?;
+
+        let (mint_in, mint_out) = match x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x)
+        };
+
+        // exactly `amount_out` has to reach the user, transfer fees on both legs come on top
+        let sent_out = amount_before_fee(mint_out, amount_out)?;
+        let res = swap_exact_out(&*curve, x, sent_out, fee)?;
+        let amount_in = amount_before_fee(mint_in, res.amount_in)?;
+
+        // Check for slippage
+        require!(amount_in <= max_in, AMMErrorCode::SlippageExceeded);
+
+        let sol = self.native_side(native)?;
+        if sol == Some(x) {
+            self.wrap_sol(x, amount_in)?;
+        }
+
+        self.deposit_token(x, amount_in)?;
+        self.withdraw_token(!x, sent_out)?;
+
+        if let Some(sol_x) = sol {
+            self.unwrap_sol(sol_x)?;
+        }
+
+        self.settle_swap(x, res.amount_in, sent_out, res.fee, now)
+    }
+
+    // books the protocol fee, feeds the volatility accumulator and emits the trade.
+    // reserves are derived from the pre-trade balances, the vault accounts are not reloaded
+    pub fn settle_swap(
+        &mut self,
+        x: bool,
+        amount_in: u64,
+        amount_out: u64,
+        fee: u64,
+        now: i64
+    ) -> Result<()> {
+        let before = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let protocol_fee = self.config.accrue_protocol_fee(x, fee)?;
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let (reserve_x, reserve_y) = match x {
+            true => (
+                reserve_x.checked_add(amount_in).ok_or(AMMErrorCode::Overflow)?,
+                reserve_y.checked_sub(amount_out).ok_or(AMMErrorCode::Underflow)?
+            ),
+            false => (
+                reserve_x.checked_sub(amount_out).ok_or(AMMErrorCode::Underflow)?,
+                reserve_y.checked_add(amount_in).ok_or(AMMErrorCode::Overflow)?
+            )
+        };
+        self.config.update_volatility(now, before, (reserve_x, reserve_y))?;
+
+        emit!(Swapped {
+            config: self.config.key(),
+            user: self.user.key(),
+            x,
+            amount_in,
+            amount_out,
+            fee,
+            protocol_fee,
+            reserve_x,
+            reserve_y,
+        });
         Ok(())
     }
 
@@ -105,19 +219,19 @@ impl<'info> Swap<'info> {
         x: bool,
         amount: u64
     ) -> Result<()>{
-        let(from, to, mint, decimals) = match x {
-            true => (self.user_x.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals),
-            false => (self.user_y.to_account_info(), self.user_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals)
+        let(from, to, mint, decimals, token_program) = match x {
+            true => (self.user_x.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.vault_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info())
         };
 
         let cpi_accounts = TransferChecked{
-            from: from,
-            to: to,
-            mint: mint,
+            from,
+            to,
+            mint,
             authority: self.user.to_account_info()
         };
 
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        let cpi_ctx = CpiContext::new(token_program, cpi_accounts);
         transfer_checked(cpi_ctx, amount, decimals)
     }
 
@@ -126,20 +240,58 @@ impl<'info> Swap<'info> {
         x: bool,
         amount: u64
     ) -> Result<()> {
-        let(from, to, mint, decimals) = match x {
-            true => (self.user_x.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals),
-            false => (self.user_y.to_account_info(), self.vault_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals)
+        let(from, to, mint, decimals, token_program) = match x {
+            true => (self.vault_x.to_account_info(), self.user_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.user_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info())
         };
 
         let cpi_accounts = TransferChecked {
-            from: from,
-            to: to,
-            mint: mint,
-            authority: self.user.to_account_info()
+            from,
+            to,
+            mint,
+            authority: self.auth.to_account_info()
         };
 
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        let config_key = self.config.key();
+        let seeds = &[
+            &b"auth"[..],
+            config_key.as_ref(),
+            &[self.config.auth_bump]
+        ];
+
+        let signer_seeds = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
         transfer_checked(cpi_ctx, amount, decimals)
     }
-}
 
+    // oracle pools price off the feed, a stale one fails the instruction
+    pub fn update_oracle_price(&mut self, now: i64) -> Result<()> {
+        let feed = self.oracle.as_ref().map(|o| o.to_account_info());
+        self.config.update_oracle_price(feed.as_ref(), self.mint_x.decimals, self.mint_y.decimals, now)
+    }
+
+    // with `native` the user's WSOL account is funded from lamports for the input
+    // and closed afterwards, so any SOL leg settles in native SOL
+    pub fn native_side(&self, native: bool) -> Result<Option<bool>> {
+        match native {
+            true => Ok(Some(native_side(&self.mint_x.key(), &self.mint_y.key())?)),
+            false => Ok(None)
+        }
+    }
+
+    pub fn wrap_sol(&self, x: bool, amount: u64) -> Result<()> {
+        let (account, token_program) = match x {
+            true => (self.user_x.to_account_info(), self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.token_program_y.to_account_info())
+        };
+        wrap_sol(self.user.to_account_info(), account, self.system_program.to_account_info(), token_program, amount)
+    }
+
+    pub fn unwrap_sol(&self, x: bool) -> Result<()> {
+        let (account, token_program) = match x {
+            true => (self.user_x.to_account_info(), self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.token_program_y.to_account_info())
+        };
+        unwrap_sol(self.user.to_account_info(), account, token_program)
+    }
+}
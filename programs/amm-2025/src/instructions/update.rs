@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::{state::Config};
+use crate::{constants::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP, MIN_RAMP_DURATION}, curves::CurveType, error::AMMErrorCode, events::{AmpRampUpdated, ConfigUpdated, PoolLocked, PoolUnlocked}, state::Config};
 
 
 #[derive(Accounts)]
@@ -9,9 +9,13 @@ pub struct Update<'info>{
     pub user: Signer<'info>,
 
     #[account(
+        mut,
         seeds=[
             b"config",
-            config.seed.to_le_bytes().as_ref()
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee_tier.as_ref(),
+            &[config.curve_type as u8]
         ],
         bump = config.config_bump
     )]
@@ -21,27 +25,212 @@ pub struct Update<'info>{
 
 impl<'info> Update<'info>  {
     pub fn lock(&mut self) -> Result<()> {
-        require_keys_eq!(
-            self.config.authority,
-            self.user.key(),
-            crate::error::AMMErrorCode::InvalidAuth
-        );
+        self.check_authority()?;
 
         self.config.locked = true;
+
+        emit!(PoolLocked {
+            config: self.config.key(),
+            authority: self.user.key(),
+        });
         Ok(())
     }
 
     pub fn unlock(&mut self) -> Result<()> {
-        require_keys_eq!(
-            self.config.authority,
-            self.user.key(),
-            crate::error::AMMErrorCode::InvalidAuth
-        );
+        self.check_authority()?;
 
         self.config.locked = false;
+
+        emit!(PoolUnlocked {
+            config: self.config.key(),
+            authority: self.user.key(),
+        });
+        Ok(())
+    }
+
+    // with a delay set the fee is only queued, swaps pick it up once it is effective
+    pub fn set_fee(&mut self, fee: u16) -> Result<()> {
+        self.check_authority()?;
+        require!(fee <= self.config.max_fee, AMMErrorCode::InvalidFee);
+
+        self.config.queue_fee(fee, Clock::get()?.unix_timestamp)?;
+        self.emit_config_updated();
         Ok(())
     }
-}
 
+    // the cap starts at the tier fee and can only come down
+    pub fn set_fee_limits(&mut self, max_fee: u16, fee_delay: i64) -> Result<()> {
+        self.check_authority()?;
+        require!(
+            max_fee <= self.config.max_fee && self.config.fee <= max_fee && self.config.max_dynamic_fee <= max_fee,
+            AMMErrorCode::InvalidFee
+        );
+        // a queued fee above the new cap would still land at fee_effective_at
+        require!(self.config.pending_fee.is_none_or(|fee| fee <= max_fee), AMMErrorCode::InvalidFee);
+        // shortening the notice period would let a fee change skip it
+        require!(fee_delay >= self.config.fee_delay, AMMErrorCode::InvalidFeeDelay);
 
+        self.config.max_fee = max_fee;
+        self.config.fee_delay = fee_delay;
+        self.emit_config_updated();
+        Ok(())
+    }
+
+    // bounds are held to the same cap as set_fee
+    pub fn set_dynamic_fee(
+        &mut self,
+        enabled: bool,
+        base_fee: u16,
+        max_dynamic_fee: u16,
+        volatility_cap: u64,
+        volatility_decay: i64
+    ) -> Result<()> {
+        self.check_authority()?;
+        require!(
+            base_fee <= max_dynamic_fee && max_dynamic_fee <= self.config.max_fee,
+            AMMErrorCode::InvalidFee
+        );
+        require!(volatility_cap > 0 && volatility_decay > 0, AMMErrorCode::InvalidVolatilityParams);
 
+        // volatility is not tracked while dynamic mode is off, switching it on starts from calm
+        if enabled && !self.config.dynamic_fee {
+            self.config.volatility = 0;
+            self.config.last_swap_ts = Clock::get()?.unix_timestamp;
+        }
+        self.config.dynamic_fee = enabled;
+        self.config.base_fee = base_fee;
+        self.config.max_dynamic_fee = max_dynamic_fee;
+        self.config.volatility_cap = volatility_cap;
+        self.config.volatility_decay = volatility_decay;
+        self.emit_config_updated();
+        Ok(())
+    }
+
+    pub fn set_protocol_fee(&mut self, protocol_fee: u16, treasury: Pubkey) -> Result<()> {
+        self.check_authority()?;
+        require!(protocol_fee <= 10000, AMMErrorCode::InvalidFee);
+
+        self.config.protocol_fee = protocol_fee;
+        self.config.treasury = treasury;
+        self.emit_config_updated();
+        Ok(())
+    }
+
+    // moves A linearly from its current value to `amp` by `stop_ts`, swaps read it at their own timestamp
+    pub fn ramp_amp(&mut self, amp: u64, stop_ts: i64) -> Result<()> {
+        self.check_authority()?;
+        require!(self.config.curve_type == CurveType::StableSwap, AMMErrorCode::InvalidCurveType);
+        require!((MIN_AMP..=MAX_AMP).contains(&amp), AMMErrorCode::InvalidAmp);
+
+        let now = Clock::get()?.unix_timestamp;
+        let current = self.config.amp(now);
+        require!(
+            stop_ts >= now.checked_add(MIN_RAMP_DURATION).ok_or(AMMErrorCode::Overflow)?,
+            AMMErrorCode::InvalidAmpRamp
+        );
+        require!(
+            amp <= current * MAX_AMP_CHANGE && amp * MAX_AMP_CHANGE >= current,
+            AMMErrorCode::InvalidAmpRamp
+        );
+
+        self.config.amp_initial = current;
+        self.config.amp_target = amp;
+        self.config.amp_start_ts = now;
+        self.config.amp_stop_ts = stop_ts;
+        self.emit_amp_ramp_updated();
+        Ok(())
+    }
+
+    // freezes A at its current value
+    pub fn stop_ramp_amp(&mut self) -> Result<()> {
+        self.check_authority()?;
+        require!(self.config.curve_type == CurveType::StableSwap, AMMErrorCode::InvalidCurveType);
+
+        let now = Clock::get()?.unix_timestamp;
+        let current = self.config.amp(now);
+
+        self.config.amp_initial = current;
+        self.config.amp_target = current;
+        self.config.amp_start_ts = now;
+        self.config.amp_stop_ts = now;
+        self.emit_amp_ramp_updated();
+        Ok(())
+    }
+
+    // the new authority only takes over once it signs accept_authority
+    pub fn propose_authority(&mut self, authority: Pubkey) -> Result<()> {
+        self.check_authority()?;
+
+        self.config.pending_authority = Some(authority);
+        self.emit_config_updated();
+        Ok(())
+    }
+
+    pub fn accept_authority(&mut self) -> Result<()> {
+        require!(self.config.authority.is_some(), AMMErrorCode::NoAuthSet);
+        require!(
+            self.config.pending_authority == Some(self.user.key()),
+            AMMErrorCode::InvalidAuth
+        );
+
+        self.config.authority = Some(self.user.key());
+        self.config.pending_authority = None;
+        self.emit_config_updated();
+        Ok(())
+    }
+
+    // one way, once renounced fee and lock parameters can never change again
+    pub fn renounce_authority(&mut self) -> Result<()> {
+        self.check_authority()?;
+        // oracle pools lock on a stale feed and need an authority to unlock
+        require!(self.config.curve_type != CurveType::Oracle, AMMErrorCode::InvalidCurveType);
+
+        self.config.authority = None;
+        self.config.pending_authority = None;
+        self.emit_config_updated();
+        Ok(())
+    }
+
+    // every admin path goes through here, a pool without authority is immutable
+    pub fn check_authority(&self) -> Result<()> {
+        match self.config.authority {
+            Some(authority) => require_keys_eq!(
+                authority,
+                self.user.key(),
+                AMMErrorCode::InvalidAuth
+            ),
+            None => return err!(AMMErrorCode::NoAuthSet),
+        }
+        Ok(())
+    }
+
+    pub fn emit_amp_ramp_updated(&self) {
+        emit!(AmpRampUpdated {
+            config: self.config.key(),
+            authority: self.user.key(),
+            amp_initial: self.config.amp_initial,
+            amp_target: self.config.amp_target,
+            amp_start_ts: self.config.amp_start_ts,
+            amp_stop_ts: self.config.amp_stop_ts,
+        });
+    }
+
+    pub fn emit_config_updated(&self) {
+        emit!(ConfigUpdated {
+            config: self.config.key(),
+            user: self.user.key(),
+            authority: self.config.authority,
+            pending_authority: self.config.pending_authority,
+            fee: self.config.fee,
+            max_fee: self.config.max_fee,
+            fee_delay: self.config.fee_delay,
+            pending_fee: self.config.pending_fee,
+            fee_effective_at: self.config.fee_effective_at,
+            dynamic_fee: self.config.dynamic_fee,
+            base_fee: self.config.base_fee,
+            max_dynamic_fee: self.config.max_dynamic_fee,
+            protocol_fee: self.config.protocol_fee,
+            treasury: self.config.treasury,
+        });
+    }
+}
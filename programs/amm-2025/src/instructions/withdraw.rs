@@ -1,80 +1,96 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{burn, transfer_checked, Burn, Mint, Token, TokenAccount, Transfer, TransferChecked},
+    token_interface::{burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::{assert_non_zero, assert_not_expired, assert_not_locked, error::AMMErrorCode, state::Config};
+use crate::{assert_non_zero, assert_not_expired, assert_not_locked, curves::withdraw_single, error::AMMErrorCode, events::{LiquidityRemoved, Swapped}, state::Config, token::{amount_after_fee, native_side, unwrap_sol}};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    pub mint_x: Box<Account<'info, Mint>>,
-    pub mint_y: Box<Account<'info, Mint>>,
+    #[account(mint::token_program=token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         seeds=[b"liquidity", config.key().as_ref()],
-        bump=config.lp_bump
+        bump=config.lp_bump,
+        mint::token_program=token_program
     )]
-    pub mint_lp: Box<Account<'info, Mint>>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint=mint_x,
-        associated_token::authority=auth
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_x,
     )]
-    pub vault_x: Box<Account<'info, TokenAccount>>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint=mint_y,
-        associated_token::authority=auth
+        associated_token::authority=auth,
+        associated_token::token_program=token_program_y,
     )]
-    pub vault_y: Box<Account<'info, TokenAccount>>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_x,
         associated_token::authority=user,
+        associated_token::token_program=token_program_x,
     )]
-    pub user_x: Box<Account<'info, TokenAccount>>,
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_y,
         associated_token::authority=user,
+        associated_token::token_program=token_program_y,
     )]
-    pub user_y: Box<Account<'info, TokenAccount>>,
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer=user,
         associated_token::mint=mint_lp,
-        associated_token::authority=user
+        associated_token::authority=user,
+        associated_token::token_program=token_program,
     )]
-    pub user_lp: Box<Account<'info, TokenAccount>>,
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: this is safe
-    #[account(seeds=[b"auth"], bump=config.auth_bump)]
+    #[account(seeds=[b"auth", config.key().as_ref()], bump=config.auth_bump)]
     pub auth: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         has_one=mint_x,
         has_one=mint_y,
         seeds = [
             b"config",
-            config.seed.to_le_bytes().as_ref()
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee_tier.as_ref(),
+            &[config.curve_type as u8]
         ],
         bump= config.config_bump
     )]
     pub config: Account<'info, Config>,
 
-    pub token_program: Program<'info, Token>,
+    /// CHECK: price feed of oracle pools, matched against config.oracle when it is read
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 
@@ -87,26 +103,152 @@ impl<'info> Withdraw<'info> {
         amount: u64,
         min_x: u64,
         min_y: u64,
-        expiration: i64
+        expiration: i64,
+        native: bool
     ) -> Result<()> {
         assert_not_locked!(self.config.locked);
         assert_not_expired!(expiration);
         assert_non_zero!([amount]);
 
-        let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
-            self.vault_x.amount,
-            self.vault_y.amount,
-            self.mint_lp.supply,
-            amount,
-            6
-        ).map_err(AMMErrorCode::from)?;
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        let (amount_x, amount_y) = self.config
+            .curve(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?
+            .withdraw_amounts(self.mint_lp.supply, amount)?;
 
-        // Check for slippage
-        require!(min_x <= amounts.x && min_y <= amounts.y, AMMErrorCode::SlippageExceeded);
+        // Check for slippage against what the user receives after transfer fees
+        require!(
+            min_x <= amount_after_fee(&self.mint_x, amount_x)? && min_y <= amount_after_fee(&self.mint_y, amount_y)?,
+            AMMErrorCode::SlippageExceeded
+        );
         
-        self.withdraw_tokens(true, amounts.x)?;
-        self.withdraw_tokens(false, amounts.y)?;
-        self.burn_lp_tokens(amount)
+        self.withdraw_tokens(true, amount_x)?;
+        self.withdraw_tokens(false, amount_y)?;
+        self.burn_lp_tokens(amount)?;
+        if native {
+            self.unwrap_sol(native_side(&self.mint_x.key(), &self.mint_y.key())?)?;
+        }
+
+        emit!(LiquidityRemoved {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            amount_lp: amount,
+            reserve_x: reserve_x.checked_sub(amount_x).ok_or(AMMErrorCode::Underflow)?,
+            reserve_y: reserve_y.checked_sub(amount_y).ok_or(AMMErrorCode::Underflow)?,
+        });
+        Ok(())
+    }
+
+    // burns LP for its proportional share and swaps the unwanted side into the wanted one
+    // inside the pool, only the wanted token leaves the vaults
+    pub fn withdraw_single(
+        &mut self,
+        amount: u64,
+        to_x: bool,
+        min_out: u64,
+        expiration: i64,
+        native: bool
+    ) -> Result<()> {
+        assert_not_locked!(self.config.locked);
+        assert_not_expired!(expiration);
+        assert_non_zero!([amount]);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.config.apply_pending_fee(now);
+        let fee = self.config.swap_fee(now);
+        self.update_oracle_price(now)?;
+
+        let (reserve_x, reserve_y) = self.config.reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let supply = self.mint_lp.supply;
+
+        let config = &self.config;
+        let res = withdraw_single(
+            |x, y| config.curve(x, y, now),
+            reserve_x,
+            reserve_y,
+            supply,
+            amount,
+            to_x,
+            fee
+        )?;
+        let (amount_x, amount_y) = (res.amount_x, res.amount_y);
+
+        // pool once the LP share is taken out, the swap runs against this
+        let burnt_x = reserve_x.checked_sub(amount_x).ok_or(AMMErrorCode::Underflow)?;
+        let burnt_y = reserve_y.checked_sub(amount_y).ok_or(AMMErrorCode::Underflow)?;
+
+        let (kept, swapped) = match to_x {
+            true => (amount_x, amount_y),
+            false => (amount_y, amount_x)
+        };
+
+        let (amount_out, swap_fee, protocol_fee) = match res.swap {
+            None => (kept, 0, 0),
+            Some(swap) => {
+                let protocol_fee = self.config.accrue_protocol_fee(!to_x, swap.fee)?;
+                (kept.checked_add(swap.amount_out).ok_or(AMMErrorCode::Overflow)?, swap.fee, protocol_fee)
+            }
+        };
+
+        let mint_out = match to_x {
+            true => &self.mint_x,
+            false => &self.mint_y
+        };
+
+        // Check for slippage against what the user receives after transfer fees
+        require!(min_out <= amount_after_fee(mint_out, amount_out)?, AMMErrorCode::SlippageExceeded);
+
+        self.withdraw_tokens(to_x, amount_out)?;
+        self.burn_lp_tokens(amount)?;
+        if native {
+            self.unwrap_sol(native_side(&self.mint_x.key(), &self.mint_y.key())?)?;
+        }
+
+        // the unwanted side stays in its vault, minus what the protocol booked
+        let swap_out = amount_out.checked_sub(kept).ok_or(AMMErrorCode::Underflow)?;
+        let (reserve_kept, reserve_swapped) = match to_x {
+            true => (burnt_x, burnt_y),
+            false => (burnt_y, burnt_x)
+        };
+        let reserve_kept = reserve_kept.checked_sub(swap_out).ok_or(AMMErrorCode::Underflow)?;
+        let reserve_swapped = reserve_swapped
+            .checked_add(swapped)
+            .ok_or(AMMErrorCode::Overflow)?
+            .checked_sub(protocol_fee)
+            .ok_or(AMMErrorCode::Underflow)?;
+        let (after_x, after_y) = match to_x {
+            true => (reserve_kept, reserve_swapped),
+            false => (reserve_swapped, reserve_kept)
+        };
+
+        emit!(LiquidityRemoved {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            amount_lp: amount,
+            reserve_x: burnt_x,
+            reserve_y: burnt_y,
+        });
+
+        if swapped > 0 {
+            self.config.update_volatility(now, (burnt_x, burnt_y), (after_x, after_y))?;
+
+            emit!(Swapped {
+                config: self.config.key(),
+                user: self.user.key(),
+                x: !to_x,
+                amount_in: swapped,
+                amount_out: swap_out,
+                fee: swap_fee,
+                protocol_fee,
+                reserve_x: after_x,
+                reserve_y: after_y,
+            });
+        }
+        Ok(())
     }
 
     pub fn withdraw_tokens(
@@ -114,19 +256,27 @@ impl<'info> Withdraw<'info> {
         x: bool,
         amount: u64
     ) -> Result<()>{
-        let (from, to, mint, decimals) = match x {
-            true => (self.vault_x.to_account_info(), self.user_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals),
-            false => (self.vault_y.to_account_info(), self.user_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals),
+        let (from, to, mint, decimals, token_program) = match x {
+            true => (self.vault_x.to_account_info(), self.user_x.to_account_info(), self.mint_x.to_account_info(), self.mint_x.decimals, self.token_program_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.user_y.to_account_info(), self.mint_y.to_account_info(), self.mint_y.decimals, self.token_program_y.to_account_info()),
         };
 
         let cpi_accounts = TransferChecked {
             from,
             mint,
             to,
-            authority: self.user.to_account_info(),
+            authority: self.auth.to_account_info(),
         };
 
-        let ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        let config_key = self.config.key();
+        let seeds = &[
+            &b"auth"[..],
+            config_key.as_ref(),
+            &[self.config.auth_bump]
+        ];
+
+        let signer_seeds = &[&seeds[..]];
+        let ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
         transfer_checked(ctx, amount, decimals)
     }
 
@@ -144,4 +294,19 @@ impl<'info> Withdraw<'info> {
         
         burn(cpi_ctx, amount)
     }
-}
\ No newline at end of file
+
+    // oracle pools price off the feed, a stale one fails the instruction
+    pub fn update_oracle_price(&mut self, now: i64) -> Result<()> {
+        let feed = self.oracle.as_ref().map(|o| o.to_account_info());
+        self.config.update_oracle_price(feed.as_ref(), self.mint_x.decimals, self.mint_y.decimals, now)
+    }
+
+    // with `native` the user's WSOL account is closed once funded, paying out native SOL
+    pub fn unwrap_sol(&self, x: bool) -> Result<()> {
+        let (account, token_program) = match x {
+            true => (self.user_x.to_account_info(), self.token_program_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.token_program_y.to_account_info())
+        };
+        unwrap_sol(self.user.to_account_info(), account, token_program)
+    }
+}
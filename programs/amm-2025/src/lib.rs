@@ -2,22 +2,29 @@ use anchor_lang::prelude::*;
 
 pub mod instructions;
 pub use instructions::*;
+pub use curves::CurveType;
 pub mod state;
 pub mod error;
 pub mod utils;
+pub mod math;
+pub mod events;
+pub mod constants;
+pub mod token;
+pub mod curves;
+pub mod tick_math;
+pub mod oracle;
 
 declare_id!("38FqduQst5K8zRo62mBo4sduwxydZosuZs6v3AdAmKm6");
 
 #[program]
 pub mod amm_2025 {
     use super::*;
-    pub fn initialize(
-        ctx: Context<Initialize>,
-        seed: u64,
-        fee: u16,
-        authority: Pubkey
-    ) -> Result<()> {
-        ctx.accounts.init(ctx.bumps, seed, fee, authority)
+    pub fn create_fee_tier(ctx: Context<CreateFeeTier>, fee: u16) -> Result<()> {
+        ctx.accounts.create_fee_tier(ctx.bumps, fee)
+    }
+
+    pub fn initialize(ctx: Context<Initialize>, params: InitParams) -> Result<()> {
+        ctx.accounts.init(ctx.bumps, params)
     }
 
     pub fn deposit(
@@ -25,9 +32,21 @@ pub mod amm_2025 {
         amount: u64,
         max_x: u64,
         max_y: u64,
-        expiration: i64
+        expiration: i64,
+        native: bool
     ) -> Result<()>{
-        ctx.accounts.deposit(amount, max_x, max_y, expiration)
+        ctx.accounts.deposit(amount, max_x, max_y, expiration, native)
+    }
+
+    pub fn deposit_single(
+        ctx: Context<Deposit>,
+        is_x: bool,
+        amount_in: u64,
+        min_lp: u64,
+        expiration: i64,
+        native: bool
+    ) -> Result<()>{
+        ctx.accounts.deposit_single(is_x, amount_in, min_lp, expiration, native)
     }
 
     pub fn withdraw(
@@ -35,9 +54,21 @@ pub mod amm_2025 {
         amount: u64,
         min_x: u64,
         min_y: u64,
-        expiration: i64
+        expiration: i64,
+        native: bool
     ) -> Result<()> {
-        ctx.accounts.withdraw(amount, min_x, min_y, expiration)
+        ctx.accounts.withdraw(amount, min_x, min_y, expiration, native)
+    }
+
+    pub fn withdraw_single(
+        ctx: Context<Withdraw>,
+        amount: u64,
+        to_x: bool,
+        min_out: u64,
+        expiration: i64,
+        native: bool
+    ) -> Result<()> {
+        ctx.accounts.withdraw_single(amount, to_x, min_out, expiration, native)
     }
 
     pub fn swap(
@@ -45,9 +76,176 @@ pub mod amm_2025 {
         x: bool,
         amount: u64,
         min: u64,
-        expiration: i64
+        expiration: i64,
+        native: bool
+    ) -> Result<()>{
+        ctx.accounts.swap(x, amount, min, expiration, native)
+    }
+
+    pub fn swap_exact_out(
+        ctx: Context<Swap>,
+        x: bool,
+        amount_out: u64,
+        max_in: u64,
+        expiration: i64,
+        native: bool
     ) -> Result<()>{
-        ctx.accounts.swap(x, amount, min, expiration)
+        ctx.accounts.swap_exact_out(x, amount_out, max_in, expiration, native)
+    }
+
+    // quotes are returned through set_return_data, meant to be simulated
+    pub fn quote_swap(
+        ctx: Context<Quote>,
+        x: bool,
+        amount: u64,
+        min: u64,
+        expiration: i64
+    ) -> Result<SwapQuote> {
+        ctx.accounts.quote_swap(x, amount, min, expiration)
+    }
+
+    pub fn quote_deposit(
+        ctx: Context<Quote>,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64
+    ) -> Result<LiquidityQuote> {
+        ctx.accounts.quote_deposit(amount, max_x, max_y, expiration)
+    }
+
+    pub fn quote_withdraw(
+        ctx: Context<Quote>,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64
+    ) -> Result<LiquidityQuote> {
+        ctx.accounts.quote_withdraw(amount, min_x, min_y, expiration)
+    }
+
+    pub fn create_cl_pool(ctx: Context<CreateClPool>, tick_spacing: u16, sqrt_price: u128) -> Result<()> {
+        ctx.accounts.create_cl_pool(ctx.bumps, tick_spacing, sqrt_price)
+    }
+
+    pub fn init_tick(ctx: Context<InitTick>, index: i32) -> Result<()> {
+        ctx.accounts.init_tick(ctx.bumps, index)
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        ctx.accounts.open_position(ctx.bumps, tick_lower, tick_upper)
+    }
+
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        ctx.accounts.close_position()
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64
+    ) -> Result<()> {
+        ctx.accounts.increase_liquidity(liquidity, max_x, max_y, expiration)
+    }
+
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64
+    ) -> Result<()> {
+        ctx.accounts.decrease_liquidity(liquidity, min_x, min_y, expiration)
+    }
+
+    pub fn collect_position_fees(ctx: Context<ModifyLiquidity>) -> Result<()> {
+        ctx.accounts.collect_position_fees()
+    }
+
+    // the ticks crossed by the swap go in the remaining accounts, in crossing order
+    pub fn cl_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+        x: bool,
+        amount: u64,
+        min: u64,
+        sqrt_price_limit: u128,
+        expiration: i64
+    ) -> Result<()> {
+        ctx.accounts.cl_swap(ctx.remaining_accounts, x, amount, min, sqrt_price_limit, expiration)
+    }
+
+    pub fn cl_lock(ctx: Context<ClUpdate>) -> Result<()> {
+        ctx.accounts.cl_lock()
+    }
+
+    pub fn cl_unlock(ctx: Context<ClUpdate>) -> Result<()> {
+        ctx.accounts.cl_unlock()
+    }
+
+    // permissionless, locks an oracle pool whose feed went stale
+    pub fn refresh_oracle(ctx: Context<RefreshOracle>) -> Result<()> {
+        ctx.accounts.refresh_oracle()
+    }
+
+    pub fn lock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.lock()
+    }
+
+    pub fn unlock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.unlock()
+    }
+
+    pub fn set_fee(ctx: Context<Update>, fee: u16) -> Result<()> {
+        ctx.accounts.set_fee(fee)
+    }
+
+    pub fn set_fee_limits(ctx: Context<Update>, max_fee: u16, fee_delay: i64) -> Result<()> {
+        ctx.accounts.set_fee_limits(max_fee, fee_delay)
+    }
+
+    pub fn set_dynamic_fee(
+        ctx: Context<Update>,
+        enabled: bool,
+        base_fee: u16,
+        max_dynamic_fee: u16,
+        volatility_cap: u64,
+        volatility_decay: i64
+    ) -> Result<()> {
+        ctx.accounts.set_dynamic_fee(enabled, base_fee, max_dynamic_fee, volatility_cap, volatility_decay)
+    }
+
+    pub fn ramp_amp(ctx: Context<Update>, amp: u64, stop_ts: i64) -> Result<()> {
+        ctx.accounts.ramp_amp(amp, stop_ts)
+    }
+
+    pub fn stop_ramp_amp(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.stop_ramp_amp()
+    }
+
+    pub fn set_protocol_fee(
+        ctx: Context<Update>,
+        protocol_fee: u16,
+        treasury: Pubkey
+    ) -> Result<()> {
+        ctx.accounts.set_protocol_fee(protocol_fee, treasury)
+    }
+
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        ctx.accounts.collect_protocol_fees()
+    }
+
+    pub fn propose_authority(ctx: Context<Update>, authority: Pubkey) -> Result<()> {
+        ctx.accounts.propose_authority(authority)
+    }
+
+    pub fn accept_authority(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.accept_authority()
+    }
+
+    pub fn renounce_authority(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.renounce_authority()
     }
 }
 
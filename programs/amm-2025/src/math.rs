@@ -0,0 +1,312 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MINIMUM_INITIAL_LIQUIDITY, curves::Q64, error::AMMErrorCode};
+
+// how far the execution price lands below `spot` (x in y, Q64.64), in basis points
+pub fn price_impact(
+    spot: u128,
+    x: bool,
+    amount_in: u64,
+    amount_out: u64
+) -> Result<u64> {
+    require!(amount_in > 0 && spot > 0, AMMErrorCode::ZeroBalance);
+
+    // what `amount_in` would buy at the spot price
+    let fair_out = match x {
+        true => mul_div(amount_in as u128, spot, Q64),
+        false => mul_div(amount_in as u128, Q64, spot)
+    }.ok_or(AMMErrorCode::Overflow)?;
+    require!(fair_out > 0, AMMErrorCode::ZeroBalance);
+
+    let ratio = amount_out as u128 * 10000 / fair_out;
+    Ok(10000u64.saturating_sub(ratio.min(10000) as u64))
+}
+
+// relative move between two spot prices, in basis points
+pub fn price_change(
+    before: u128,
+    after: u128
+) -> Result<u64> {
+    require!(before > 0, AMMErrorCode::ZeroBalance);
+
+    let change = mul_div(before.abs_diff(after), 10000, before).ok_or(AMMErrorCode::Overflow)?;
+    Ok(change.min(u64::MAX as u128) as u64)
+}
+
+// floor(sqrt(n)) by Newton's method
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // 2^ceil(bits / 2) is never below the root, Newton walks down from there
+    let mut x = 1u128 << (128 - n.leading_zeros()).div_ceil(2);
+    let mut y = (x + n / x) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+// LP for the first deposit, the geometric mean of both sides
+pub fn initial_liquidity(x: u64, y: u64) -> Result<u64> {
+    let liquidity = isqrt(x as u128 * y as u128);
+    require!(liquidity >= MINIMUM_INITIAL_LIQUIDITY as u128, AMMErrorCode::InsufficientInitialLiquidity);
+
+    Ok(liquidity as u64)
+}
+
+// floor(a * b / c) through a 256 bit intermediate, None when c is zero or the result overflows
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+
+    // 128 x 128 -> 256 bit product split in 64 bit limbs
+    let (a0, a1) = (a as u64 as u128, a >> 64);
+    let (b0, b1) = (b as u64 as u128, b >> 64);
+    let (ll, lh, hl, hh) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let mid = (ll >> 64) + (lh as u64 as u128) + (hl as u64 as u128);
+    let lo = (ll as u64 as u128) | (mid << 64);
+    let hi = hh + (lh >> 64) + (hl >> 64) + (mid >> 64);
+
+    if hi >= c {
+        return None;
+    }
+
+    // long division of (hi, lo) by c, one bit at a time
+    let mut rem = hi;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || rem >= c {
+            rem = rem.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+// ceil(a * b / c), same limits as mul_div
+pub fn mul_div_ceil(a: u128, b: u128, c: u128) -> Option<u128> {
+    let quotient = mul_div(a, b, c)?;
+    // a * b - q * c is the remainder, below c so the wrapping difference is exact
+    match a.wrapping_mul(b).wrapping_sub(quotient.wrapping_mul(c)) {
+        0 => Some(quotient),
+        _ => quotient.checked_add(1)
+    }
+}
+
+// floor(sqrt(a * b)) without overflowing on the product
+pub fn sqrt_mul(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+
+    // (isqrt(a) + 1) * (isqrt(b) + 1) is never below the root, so Newton only has to walk down
+    let (ra, rb) = (isqrt(a), isqrt(b));
+    let mut x = ra.saturating_mul(rb).saturating_add(ra).saturating_add(rb).saturating_add(1);
+    loop {
+        // (x + q) / 2 written so it can not overflow, q > x means x is already the floor
+        let q = mul_div(a, b, x)?;
+        if q >= x {
+            return Some(x);
+        }
+        x = q + (x - q) / 2;
+    }
+}
+
+// part of a single sided deposit that has to be swapped so the rest matches the pool ratio,
+// solves (a - s) / (r + s) = out(s) / (y - out(s)) with the fee taken on s
+pub fn zap_swap_amount(
+    reserve_in: u64,
+    amount_in: u64,
+    fee: u16
+) -> Result<u64> {
+    require!(fee < 10000, AMMErrorCode::InvalidFee);
+
+    let f = 10000u128;
+    let g = f - fee as u128;
+    let r = reserve_in as u128;
+
+    // s = (sqrt(r * (r * (f + g)^2 + 4 * a * g * f)) - r * (f + g)) / 2g
+    let inner = r
+        .checked_mul((f + g) * (f + g))
+        .and_then(|v| v.checked_add(amount_in as u128 * 4 * g * f))
+        .ok_or(AMMErrorCode::Overflow)?;
+    let root = sqrt_mul(r, inner).ok_or(AMMErrorCode::Overflow)?;
+    let swap = root.saturating_sub(r * (f + g)) / (2 * g);
+
+    Ok((swap as u64).min(amount_in))
+}
+
+// bits of the exponent's fractional part `pow_q64` resolves
+const POW_FRACTION_BITS: u32 = 32;
+
+// base^(num / den) for a Q64.64 base in (0, 1], rounded up or down as asked.
+// the integer part of the exponent goes by squaring, the fraction bit by bit through repeated square roots
+pub fn pow_q64(base: u128, num: u64, den: u64, round_up: bool) -> Option<u128> {
+    if base > Q64 || den == 0 {
+        return None;
+    }
+
+    let mut result = Q64;
+    let (mut whole, mut rem) = (num / den, num % den);
+
+    let mut square = base;
+    while whole > 0 {
+        if whole & 1 == 1 {
+            result = mul_q64(result, square, round_up);
+        }
+        square = mul_q64(square, square, round_up);
+        whole >>= 1;
+    }
+
+    let mut root = base;
+    for _ in 0..POW_FRACTION_BITS {
+        if rem == 0 {
+            break;
+        }
+        root = sqrt_q64(root, round_up);
+        rem <<= 1;
+        if rem >= den {
+            rem -= den;
+            result = mul_q64(result, root, round_up);
+        }
+    }
+
+    // what is left of the exponent is below the last root's, multiplying by that root bounds it from below
+    if rem > 0 && !round_up {
+        result = mul_q64(result, root, false);
+    }
+    Some(result)
+}
+
+// a * b for Q64.64 values in [0, 1]
+fn mul_q64(a: u128, b: u128, round_up: bool) -> u128 {
+    if a == Q64 || b == Q64 {
+        return a.min(b);
+    }
+
+    let product = a * b;
+    match round_up {
+        true => product.div_ceil(Q64),
+        false => product >> 64
+    }
+}
+
+// sqrt of a Q64.64 value in [0, 1]
+fn sqrt_q64(a: u128, round_up: bool) -> u128 {
+    if a == Q64 {
+        return Q64;
+    }
+
+    let n = a << 64;
+    let root = isqrt(n);
+    match round_up && root * root < n {
+        true => root + 1,
+        false => root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MINIMUM_LIQUIDITY;
+
+    #[test]
+    fn isqrt_floors() {
+        for n in [0u128, 1, 2, 3, 4, 15, 16, 17, 99, 100, 101, u64::MAX as u128, u128::MAX] {
+            let root = isqrt(n);
+            assert!(root * root <= n);
+            assert!(root.checked_add(1).and_then(|r| r.checked_mul(r)).is_none_or(|sq| sq > n));
+        }
+    }
+
+    #[test]
+    fn initial_liquidity_is_geometric_mean() {
+        assert_eq!(initial_liquidity(1_000_000, 1_000_000).unwrap(), 1_000_000);
+        assert_eq!(initial_liquidity(4_000_000, 1_000_000).unwrap(), 2_000_000);
+        // floored, never more LP than the deposit backs
+        assert_eq!(initial_liquidity(20_000, 20_001).unwrap(), 20_000);
+        assert_eq!(initial_liquidity(u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn initial_liquidity_covers_the_locked_minimum() {
+        assert!(initial_liquidity(MINIMUM_INITIAL_LIQUIDITY - 1, MINIMUM_INITIAL_LIQUIDITY - 1).is_err());
+        assert!(initial_liquidity(1, u64::MAX).is_ok());
+
+        // the smallest accepted first deposit still leaves LP for the user after the lock
+        let lp = initial_liquidity(MINIMUM_INITIAL_LIQUIDITY, MINIMUM_INITIAL_LIQUIDITY).unwrap();
+        assert_eq!(lp, MINIMUM_INITIAL_LIQUIDITY);
+        assert!(lp - MINIMUM_LIQUIDITY > 0);
+    }
+
+    // little endian u32 limbs of base^exp * 2^(64 * shift), exact where u128 would not be
+    fn big_pow(base: u128, exp: u64, shift: usize) -> Vec<u64> {
+        let mut limbs = vec![1u64];
+        for _ in 0..exp {
+            let mut product = vec![0u64; limbs.len() + 4];
+            for (i, limb) in limbs.iter().enumerate() {
+                let mut carry = 0u64;
+                for j in 0..4 {
+                    let digit = (base >> (32 * j)) as u64 & 0xffff_ffff;
+                    let v = product[i + j] + limb * digit + carry;
+                    product[i + j] = v & 0xffff_ffff;
+                    carry = v >> 32;
+                }
+                product[i + 4] += carry;
+            }
+            limbs = product;
+        }
+        let mut shifted = vec![0u64; shift * 2];
+        shifted.extend(limbs);
+        while shifted.len() > 1 && shifted.last() == Some(&0) {
+            shifted.pop();
+        }
+        shifted
+    }
+
+    // a^q * Q64^p against base^p * Q64^q, i.e. a against base^(p / q) without rounding
+    fn cmp_root(a: u128, base: u128, p: u64, q: u64) -> std::cmp::Ordering {
+        let lhs = big_pow(a, q, p as usize);
+        let rhs = big_pow(base, p, q as usize);
+        lhs.len().cmp(&rhs.len()).then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+    }
+
+    #[test]
+    fn pow_q64_brackets_the_exact_power() {
+        // weight pairs as the weighted curve passes them, with the reduced exponent the check raises to
+        let weights = [
+            (8000, 2000, 4, 1),
+            (2000, 8000, 1, 4),
+            (5000, 5000, 1, 1),
+            (3000, 7000, 3, 7),
+            (7000, 3000, 7, 3),
+            (9000, 1000, 9, 1),
+            (1000, 9000, 1, 9),
+            (6000, 4000, 3, 2),
+            (2500, 7500, 1, 3),
+        ];
+        let bases = [Q64, Q64 - 1, Q64 / 2, Q64 / 3, Q64 * 999 / 1000, Q64 / 1_000_000, 1 << 32, 1];
+
+        for (num, den, p, q) in weights {
+            for base in bases {
+                let up = pow_q64(base, num, den, true).unwrap();
+                let down = pow_q64(base, num, den, false).unwrap();
+                assert!(cmp_root(up, base, p, q).is_ge(), "up {base} ^ {num}/{den}");
+                assert!(cmp_root(down, base, p, q).is_le(), "down {base} ^ {num}/{den}");
+            }
+        }
+    }
+
+    #[test]
+    fn pow_q64_rejects_bases_above_one() {
+        assert!(pow_q64(Q64 + 1, 1, 1, true).is_none());
+        assert!(pow_q64(Q64, 1, 0, true).is_none());
+    }
+}
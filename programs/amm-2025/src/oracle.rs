@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+
+use crate::{curves::Q64, error::AMMErrorCode, math::mul_div};
+
+// legacy Pyth oracle program on mainnet, the only owner a feed is accepted from
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi9epH");
+
+// offsets into a legacy Pyth price account, only the aggregate price is read
+const MAGIC: u32 = 0xa1b2c3d4;
+const PRICE_ACCOUNT_TYPE: u32 = 3;
+const STATUS_TRADING: u32 = 1;
+
+const MAGIC_OFFSET: usize = 0;
+const TYPE_OFFSET: usize = 8;
+const EXPO_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const PRICE_OFFSET: usize = 208;
+const CONF_OFFSET: usize = 216;
+const STATUS_OFFSET: usize = 224;
+const MIN_LEN: usize = 240;
+
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub status: u32,
+}
+
+impl PriceFeed {
+    // the feed has to belong to Pyth, anyone can write an account with the right layout.
+    // mock-oracle builds skip the owner so tests can create feeds of their own
+    pub fn load(account: &AccountInfo) -> Result<PriceFeed> {
+        #[cfg(not(feature = "mock-oracle"))]
+        require_keys_eq!(*account.owner, PYTH_PROGRAM_ID, AMMErrorCode::InvalidOracle);
+
+        let data = account.try_borrow_data()?;
+        require!(data.len() >= MIN_LEN, AMMErrorCode::InvalidOracle);
+
+        let read = |offset: usize| -> [u8; 8] { data[offset..offset + 8].try_into().unwrap() };
+        let read_u32 = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+        require!(
+            read_u32(MAGIC_OFFSET) == MAGIC && read_u32(TYPE_OFFSET) == PRICE_ACCOUNT_TYPE,
+            AMMErrorCode::InvalidOracle
+        );
+
+        Ok(PriceFeed {
+            price: i64::from_le_bytes(read(PRICE_OFFSET)),
+            conf: u64::from_le_bytes(read(CONF_OFFSET)),
+            expo: read_u32(EXPO_OFFSET) as i32,
+            publish_time: i64::from_le_bytes(read(TIMESTAMP_OFFSET)),
+            status: read_u32(STATUS_OFFSET),
+        })
+    }
+
+    // a halted feed counts as stale
+    pub fn is_stale(&self, now: i64, max_staleness: i64) -> bool {
+        self.status != STATUS_TRADING || now.saturating_sub(self.publish_time) > max_staleness
+    }
+
+    // price of one x atom in y atoms as Q64.64, rejected when the confidence interval is
+    // wider than `max_confidence` basis points of the price
+    pub fn price_q64(&self, decimals_x: u8, decimals_y: u8, max_confidence: u16) -> Result<u128> {
+        require!(self.price > 0, AMMErrorCode::InvalidOracle);
+        let price = self.price as u128;
+        require!(
+            self.conf as u128 * 10000 <= price * max_confidence as u128,
+            AMMErrorCode::OracleConfidence
+        );
+
+        // feed prices are in whole tokens, scaled by 10^expo
+        let exponent = self.expo + decimals_y as i32 - decimals_x as i32;
+        let scale = 10u128.checked_pow(exponent.unsigned_abs()).ok_or(AMMErrorCode::Overflow)?;
+        let price = match exponent >= 0 {
+            true => price.checked_mul(scale).and_then(|p| p.checked_mul(Q64)),
+            false => mul_div(price, Q64, scale)
+        }.ok_or(AMMErrorCode::Overflow)?;
+
+        require!(price > 0, AMMErrorCode::InvalidOracle);
+        Ok(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_data(price: i64, conf: u64, expo: i32, publish_time: i64) -> Vec<u8> {
+        let mut data = vec![0u8; MIN_LEN];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&MAGIC.to_le_bytes());
+        data[TYPE_OFFSET..TYPE_OFFSET + 4].copy_from_slice(&PRICE_ACCOUNT_TYPE.to_le_bytes());
+        data[EXPO_OFFSET..EXPO_OFFSET + 4].copy_from_slice(&expo.to_le_bytes());
+        data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&publish_time.to_le_bytes());
+        data[PRICE_OFFSET..PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[CONF_OFFSET..CONF_OFFSET + 8].copy_from_slice(&conf.to_le_bytes());
+        data[STATUS_OFFSET..STATUS_OFFSET + 4].copy_from_slice(&STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    fn load_owned_by(owner: &Pubkey, data: &mut [u8]) -> Result<PriceFeed> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, data, owner, false, 0);
+        PriceFeed::load(&account)
+    }
+
+    #[test]
+    fn loads_a_pyth_feed() {
+        let mut data = feed_data(150_000_000, 50_000, -6, 1_000);
+        let feed = load_owned_by(&PYTH_PROGRAM_ID, &mut data).unwrap();
+        assert_eq!((feed.price, feed.conf, feed.expo, feed.publish_time), (150_000_000, 50_000, -6, 1_000));
+
+        assert!(!feed.is_stale(1_060, 60));
+        assert!(feed.is_stale(1_061, 60));
+        // 150.0 with equal decimals on both sides
+        assert_eq!(feed.price_q64(6, 6, 10).unwrap(), 150 * Q64);
+        assert!(feed.price_q64(6, 6, 3).is_err());
+    }
+
+    #[cfg(not(feature = "mock-oracle"))]
+    #[test]
+    fn rejects_a_feed_pyth_does_not_own() {
+        let mut data = feed_data(150_000_000, 50_000, -6, 1_000);
+        assert!(load_owned_by(&Pubkey::new_unique(), &mut data).is_err());
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let mut data = feed_data(150_000_000, 50_000, -6, 1_000);
+        data[TYPE_OFFSET] = 2;
+        assert!(load_owned_by(&PYTH_PROGRAM_ID, &mut data).is_err());
+        assert!(load_owned_by(&PYTH_PROGRAM_ID, &mut [0u8; MIN_LEN - 1]).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use anchor_lang::prelude::*;
+
+use crate::error::AMMErrorCode;
+
+use super::Tick;
+
+// concentrated liquidity pool, lives beside the full range `Config` pools with its own vaults
+#[account]
+#[derive(InitSpace)]
+pub struct ClPool {
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub fee_tier: Pubkey,
+    pub fee: u16,
+    pub tick_spacing: u16,
+    // sqrt of the y per x price, Q64.64
+    pub sqrt_price: u128,
+    pub tick_current: i32,
+    // liquidity of the positions whose range holds the current price
+    pub liquidity: u128,
+    // closest initialized ticks at or below and above the current one, None past the ends of the tick list
+    pub tick_below: Option<i32>,
+    pub tick_above: Option<i32>,
+    // fees earned per unit of liquidity over the pool's life, Q64.64, only differences are meaningful
+    pub fee_growth_x: u128,
+    pub fee_growth_y: u128,
+    // set by the fee tier admin, halts swaps and liquidity changes
+    pub locked: bool,
+    pub auth_bump: u8,
+    pub pool_bump: u8
+}
+
+impl ClPool {
+    // moves the price over `tick`, flipping its outside fee growth and picking up its liquidity
+    pub fn cross(&mut self, tick: &mut Tick, up: bool) -> Result<()> {
+        tick.fee_growth_outside_x = self.fee_growth_x.wrapping_sub(tick.fee_growth_outside_x);
+        tick.fee_growth_outside_y = self.fee_growth_y.wrapping_sub(tick.fee_growth_outside_y);
+
+        match up {
+            true => {
+                self.liquidity = add_liquidity(self.liquidity, tick.liquidity_net)?;
+                self.tick_current = tick.index;
+                self.tick_below = Some(tick.index);
+                self.tick_above = tick.next;
+            },
+            false => {
+                self.liquidity = add_liquidity(self.liquidity, -tick.liquidity_net)?;
+                self.tick_current = tick.index - 1;
+                self.tick_below = tick.prev;
+                self.tick_above = Some(tick.index);
+            }
+        }
+        Ok(())
+    }
+
+    // fee growth per unit of liquidity earned between two ticks
+    pub fn fee_growth_inside(&self, lower: &Tick, upper: &Tick) -> (u128, u128) {
+        let inside = |global: u128, lower_outside: u128, upper_outside: u128| {
+            let below = match self.tick_current >= lower.index {
+                true => lower_outside,
+                false => global.wrapping_sub(lower_outside)
+            };
+            let above = match self.tick_current < upper.index {
+                true => upper_outside,
+                false => global.wrapping_sub(upper_outside)
+            };
+            global.wrapping_sub(below).wrapping_sub(above)
+        };
+
+        (
+            inside(self.fee_growth_x, lower.fee_growth_outside_x, upper.fee_growth_outside_x),
+            inside(self.fee_growth_y, lower.fee_growth_outside_y, upper.fee_growth_outside_y)
+        )
+    }
+}
+
+pub fn add_liquidity(liquidity: u128, delta: i128) -> Result<u128> {
+    match delta >= 0 {
+        true => liquidity.checked_add(delta as u128).ok_or(error!(AMMErrorCode::Overflow)),
+        false => liquidity.checked_sub(delta.unsigned_abs()).ok_or(error!(AMMErrorCode::Underflow))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{curves::Q64, math::mul_div, tick_math::{sqrt_price_at_tick, swap_step, tick_at_sqrt_price}};
+
+    const FEE: u16 = 30;
+    // (lower, upper, liquidity)
+    const POSITIONS: [(i32, i32, u128); 2] = [(-100, 100, 1_000_000_000_000), (-100, 300, 3_000_000_000_000)];
+
+    fn tick(index: i32, prev: Option<i32>, next: Option<i32>, liquidity_net: i128) -> Tick {
+        Tick {
+            pool: Pubkey::default(),
+            index,
+            prev,
+            next,
+            liquidity_gross: liquidity_net.unsigned_abs(),
+            liquidity_net,
+            fee_growth_outside_x: 0,
+            fee_growth_outside_y: 0,
+            bump: 0
+        }
+    }
+
+    fn pool() -> (ClPool, Vec<Tick>) {
+        let (l1, l2) = (POSITIONS[0].2 as i128, POSITIONS[1].2 as i128);
+        let ticks = vec![
+            tick(-100, None, Some(100), l1 + l2),
+            tick(100, Some(-100), Some(300), -l1),
+            tick(300, Some(100), None, -l2),
+        ];
+        let pool = ClPool {
+            mint_x: Pubkey::default(),
+            mint_y: Pubkey::default(),
+            fee_tier: Pubkey::default(),
+            fee: FEE,
+            tick_spacing: 1,
+            sqrt_price: Q64,
+            tick_current: 0,
+            liquidity: (l1 + l2) as u128,
+            tick_below: Some(-100),
+            tick_above: Some(100),
+            fee_growth_x: 0,
+            fee_growth_y: 0,
+            locked: false,
+            auth_bump: 0,
+            pool_bump: 0
+        };
+        (pool, ticks)
+    }
+
+    // cl_swap's loop without the accounts, returns the fee paid and the growth each position was active for
+    fn swap(pool: &mut ClPool, ticks: &mut [Tick], x: bool, amount: u64, limit: u128) -> (u64, [u128; 2]) {
+        let mut remaining = amount;
+        let mut fee = 0u64;
+        let mut earned = [0u128; 2];
+
+        while remaining > 0 && pool.sqrt_price != limit {
+            let next_tick = match x {
+                true => pool.tick_below,
+                false => pool.tick_above
+            };
+            let tick_price = next_tick.map(|t| sqrt_price_at_tick(t).unwrap());
+            let target = match (tick_price, x) {
+                (Some(price), true) => price.max(limit),
+                (Some(price), false) => price.min(limit),
+                (None, _) => limit
+            };
+
+            let step = swap_step(pool.sqrt_price, target, pool.liquidity, remaining, FEE, x).unwrap();
+            remaining -= step.amount_in + step.fee;
+            fee += step.fee;
+
+            let growth = mul_div(step.fee as u128, Q64, pool.liquidity).unwrap();
+            match x {
+                true => pool.fee_growth_x = pool.fee_growth_x.wrapping_add(growth),
+                false => pool.fee_growth_y = pool.fee_growth_y.wrapping_add(growth)
+            }
+            for (i, (lower, upper, _)) in POSITIONS.iter().enumerate() {
+                if (*lower..*upper).contains(&pool.tick_current) {
+                    earned[i] += growth;
+                }
+            }
+
+            pool.sqrt_price = step.sqrt_price;
+            match (next_tick, tick_price == Some(step.sqrt_price)) {
+                (Some(index), true) => {
+                    let tick = ticks.iter_mut().find(|t| t.index == index).unwrap();
+                    pool.cross(tick, !x).unwrap();
+                },
+                _ => pool.tick_current = tick_at_sqrt_price(step.sqrt_price).unwrap()
+            }
+        }
+        (fee, earned)
+    }
+
+    fn inside(pool: &ClPool, ticks: &[Tick], lower: i32, upper: i32) -> (u128, u128) {
+        let find = |index: i32| ticks.iter().find(|t| t.index == index).unwrap();
+        pool.fee_growth_inside(find(lower), find(upper))
+    }
+
+    #[test]
+    fn round_trip_over_a_tick_credits_the_fees_paid() {
+        let (mut pool, mut ticks) = pool();
+
+        // y in pushes the price over tick 100, x in brings it back under
+        let (fee_y, earned_y) = swap(&mut pool, &mut ticks, false, 1_000_000_000_000, sqrt_price_at_tick(150).unwrap());
+        assert_eq!(pool.tick_current, 150);
+        assert_eq!(pool.liquidity, POSITIONS[1].2);
+        let (fee_x, earned_x) = swap(&mut pool, &mut ticks, true, 1_000_000_000_000, sqrt_price_at_tick(50).unwrap());
+        assert_eq!(pool.tick_current, 50);
+        assert_eq!(pool.liquidity, POSITIONS[0].2 + POSITIONS[1].2);
+
+        let mut paid_out = (0u128, 0u128);
+        for (i, (lower, upper, liquidity)) in POSITIONS.iter().enumerate() {
+            // the ticks' outside growth puts back exactly what accrued while the range held the price
+            let (growth_x, growth_y) = inside(&pool, &ticks, *lower, *upper);
+            assert_eq!((growth_x, growth_y), (earned_x[i], earned_y[i]));
+
+            paid_out.0 += mul_div(growth_x, *liquidity, Q64).unwrap();
+            paid_out.1 += mul_div(growth_y, *liquidity, Q64).unwrap();
+        }
+
+        // the narrow position sat out the part above tick 100
+        assert!(earned_y[0] < earned_y[1]);
+
+        // positions can claim every fee paid, less a unit per step and position of rounding
+        assert!(paid_out.0 <= fee_x as u128 && paid_out.0 + 8 >= fee_x as u128);
+        assert!(paid_out.1 <= fee_y as u128 && paid_out.1 + 8 >= fee_y as u128);
+    }
+}
@@ -1,13 +1,54 @@
 use anchor_lang::prelude::*;
 
+use crate::{curves::{ConstantProduct, Curve, CurveType, Oracle, StableSwap, Weighted}, error::AMMErrorCode, instructions::{InitParams, InitializeBumps}, math::price_change, oracle::PriceFeed, state::FeeTier};
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
-    pub seed: u64,
-    pub authority: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub pending_authority: Option<Pubkey>,
     pub mint_x: Pubkey,
     pub mint_y: Pubkey,
+    pub fee_tier: Pubkey,
+    pub curve_type: CurveType,
+    // StableSwap amplification, moves linearly from amp_initial to amp_target between the two timestamps
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub amp_start_ts: i64,
+    pub amp_stop_ts: i64,
+    // share of the pool value held by each side in basis points, 5000/5000 outside weighted pools
+    pub weight_x: u16,
+    pub weight_y: u16,
+    // price feed oracle pools trade around, Pubkey::default() for every other curve
+    pub oracle: Pubkey,
+    // seconds a feed price stays usable after it was published
+    pub max_staleness: i64,
+    // widest accepted confidence interval, in basis points of the price
+    pub max_confidence: u16,
+    // charged on top of the oracle price on both sides, in basis points
+    pub spread: u16,
+    // last accepted feed price, y per x in Q64.64
+    pub oracle_price: u128,
     pub fee: u16,
+    pub max_fee: u16,
+    // seconds between set_fee and the new fee being charged, can only grow
+    pub fee_delay: i64,
+    pub pending_fee: Option<u16>,
+    pub fee_effective_at: i64,
+    // dynamic mode charges between base_fee and max_dynamic_fee depending on volatility
+    pub dynamic_fee: bool,
+    pub base_fee: u16,
+    pub max_dynamic_fee: u16,
+    // accumulated price moves in basis points, decays linearly to zero over volatility_decay seconds
+    pub volatility: u64,
+    pub volatility_cap: u64,
+    pub volatility_decay: i64,
+    pub last_swap_ts: i64,
+    // protocol share of every swap fee, in basis points of `fee`
+    pub protocol_fee: u16,
+    pub treasury: Pubkey,
+    pub protocol_fees_x: u64,
+    pub protocol_fees_y: u64,
     pub locked: bool,
     pub auth_bump: u8,
     pub config_bump: u8,
@@ -16,26 +57,281 @@ pub struct Config {
 
 impl Config{
 
+    // pools belong to the tier admin unless created without authority, and start capped at
+    // the tier fee. whoever pays for the accounts gets no say over the pool
     pub fn init(
         &mut self,
-        seed: u64,
-        authority: Pubkey,
         mint_x: Pubkey,
         mint_y: Pubkey,
-        fee: u16,
-        auth_bump: u8,
-        config_bump: u8,
-        lp_bump: u8,
+        fee_tier: &Account<FeeTier>,
+        params: &InitParams,
+        bumps: &InitializeBumps,
     ){
-        self.seed= seed;
-        self.authority=authority;
+        let fee = fee_tier.fee;
+        self.authority=match params.renounce_authority {
+            true => None,
+            false => Some(fee_tier.admin)
+        };
+        self.pending_authority=None;
         self.mint_x= mint_x;
         self.mint_y=mint_y;
+        self.fee_tier=fee_tier.key();
+        self.curve_type=params.curve_type;
+        self.amp_initial=params.amp;
+        self.amp_target=params.amp;
+        self.amp_start_ts=0;
+        self.amp_stop_ts=0;
+        self.weight_x=params.weight_x;
+        self.weight_y=10000 - params.weight_x;
+        self.oracle=Pubkey::default();
+        self.max_staleness=params.oracle.max_staleness;
+        self.max_confidence=params.oracle.max_confidence;
+        self.spread=params.oracle.spread;
+        self.oracle_price=0;
         self.fee=fee;
+        self.max_fee=fee;
+        self.fee_delay=0;
+        self.pending_fee=None;
+        self.fee_effective_at=0;
+        self.dynamic_fee=false;
+        self.base_fee=fee;
+        self.max_dynamic_fee=fee;
+        self.volatility=0;
+        self.volatility_cap=0;
+        self.volatility_decay=0;
+        self.last_swap_ts=0;
+        self.protocol_fee=0;
+        self.treasury=Pubkey::default();
+        self.protocol_fees_x=0;
+        self.protocol_fees_y=0;
         self.locked= false;
-        self.auth_bump=auth_bump;
-        self.config_bump=config_bump;
-        self.lp_bump= lp_bump
+        self.auth_bump=bumps.auth;
+        self.config_bump=bumps.config;
+        self.lp_bump= bumps.mint_lp
+    }
+
+    // fee charged at `now`, including a queued fee whose delay has passed
+    pub fn current_fee(&self, now: i64) -> u16 {
+        match self.pending_fee {
+            Some(fee) if now >= self.fee_effective_at => fee,
+            _ => self.fee
+        }
+    }
+
+    // a queued fee that already took effect is applied first, otherwise replacing it would
+    // put the pool back on the old fee until the new delay is over
+    pub fn queue_fee(&mut self, fee: u16, now: i64) -> Result<()> {
+        self.apply_pending_fee(now);
+
+        match self.fee_delay {
+            0 => {
+                self.fee = fee;
+                self.pending_fee = None;
+            },
+            delay => {
+                self.pending_fee = Some(fee);
+                self.fee_effective_at = now.checked_add(delay).ok_or(AMMErrorCode::Overflow)?;
+            }
+        }
+        Ok(())
+    }
+
+    // swaps call this so a queued fee is applied lazily once the delay is over
+    pub fn apply_pending_fee(&mut self, now: i64) {
+        self.fee = self.current_fee(now);
+        if self.fee_effective_at <= now {
+            self.pending_fee = None;
+        }
+    }
+
+    // fee charged by a swap at `now`, scaled by recent volatility in dynamic mode
+    pub fn swap_fee(&self, now: i64) -> u16 {
+        if !self.dynamic_fee {
+            return self.current_fee(now);
+        }
+
+        let volatility = self.decayed_volatility(now).min(self.volatility_cap);
+        let range = (self.max_dynamic_fee - self.base_fee) as u128;
+        self.base_fee + (range * volatility as u128 / self.volatility_cap as u128) as u16
+    }
+
+    pub fn decayed_volatility(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.last_swap_ts);
+        if elapsed >= self.volatility_decay {
+            return 0;
+        }
+
+        (self.volatility as u128 * (self.volatility_decay - elapsed) as u128 / self.volatility_decay as u128) as u64
+    }
+
+    // adds the move of the spot price caused by a swap to the accumulator, skipped outside
+    // dynamic mode where nothing reads it
+    pub fn update_volatility(&mut self, now: i64, before: (u64, u64), after: (u64, u64)) -> Result<()> {
+        if !self.dynamic_fee {
+            return Ok(());
+        }
+
+        let change = price_change(
+            self.curve(before.0, before.1, now)?.spot_price()?,
+            self.curve(after.0, after.1, now)?.spot_price()?
+        )?;
+
+        self.volatility = self.decayed_volatility(now).saturating_add(change);
+        self.last_swap_ts = now;
+        Ok(())
+    }
+
+    // pricing of this pool over the given reserves at `now`
+    pub fn curve(&self, reserve_x: u64, reserve_y: u64, now: i64) -> Result<Box<dyn Curve>> {
+        match self.curve_type {
+            CurveType::ConstantProduct => Ok(Box::new(ConstantProduct { reserve_x, reserve_y })),
+            CurveType::StableSwap => Ok(Box::new(StableSwap { reserve_x, reserve_y, amp: self.amp(now) })),
+            CurveType::Weighted => Ok(Box::new(Weighted {
+                reserve_x,
+                reserve_y,
+                weight_x: self.weight_x,
+                weight_y: self.weight_y
+            })),
+            CurveType::Oracle => Ok(Box::new(Oracle {
+                reserve_x,
+                reserve_y,
+                price: self.oracle_price,
+                spread: self.spread
+            })),
+        }
+    }
+
+    // reads the pool's feed into `oracle_price` before anything is priced. a stale feed fails
+    // the trade, locking the pool is left to refresh_oracle since a failed trade keeps no state
+    pub fn update_oracle_price(
+        &mut self,
+        feed: Option<&AccountInfo>,
+        decimals_x: u8,
+        decimals_y: u8,
+        now: i64
+    ) -> Result<()> {
+        if self.curve_type != CurveType::Oracle {
+            return Ok(());
+        }
+
+        self.oracle_price = self
+            .read_oracle(feed, decimals_x, decimals_y, now)?
+            .ok_or(AMMErrorCode::StaleOracle)?;
+        Ok(())
+    }
+
+    // feed price as Q64.64, None when it is too old to trade on
+    pub fn read_oracle(
+        &self,
+        feed: Option<&AccountInfo>,
+        decimals_x: u8,
+        decimals_y: u8,
+        now: i64
+    ) -> Result<Option<u128>> {
+        let feed = feed.ok_or(AMMErrorCode::InvalidOracle)?;
+        require_keys_eq!(feed.key(), self.oracle, AMMErrorCode::InvalidOracle);
+
+        let price = PriceFeed::load(feed)?;
+        if price.is_stale(now, self.max_staleness) {
+            return Ok(None);
+        }
+        Ok(Some(price.price_q64(decimals_x, decimals_y, self.max_confidence)?))
+    }
+
+    // amplification at `now`, interpolated while a ramp is running
+    pub fn amp(&self, now: i64) -> u64 {
+        if now >= self.amp_stop_ts {
+            return self.amp_target;
+        }
+
+        let elapsed = now.saturating_sub(self.amp_start_ts).max(0) as u128;
+        let duration = (self.amp_stop_ts - self.amp_start_ts) as u128;
+        let delta = self.amp_initial.abs_diff(self.amp_target) as u128 * elapsed / duration;
+        match self.amp_target > self.amp_initial {
+            true => self.amp_initial + delta as u64,
+            false => self.amp_initial - delta as u64
+        }
+    }
+
+    // vault balances minus the uncollected protocol fees, this is what belongs to LPs
+    pub fn reserves(&self, vault_x: u64, vault_y: u64) -> Result<(u64, u64)> {
+        Ok((
+            vault_x.checked_sub(self.protocol_fees_x).ok_or(AMMErrorCode::Underflow)?,
+            vault_y.checked_sub(self.protocol_fees_y).ok_or(AMMErrorCode::Underflow)?
+        ))
+    }
+
+    // books the protocol cut of a swap fee paid in x or y and returns it
+    pub fn accrue_protocol_fee(&mut self, x: bool, fee: u64) -> Result<u64> {
+        let share = (fee as u128 * self.protocol_fee as u128 / 10000) as u64;
+        let accrued = match x {
+            true => &mut self.protocol_fees_x,
+            false => &mut self.protocol_fees_y
+        };
+        *accrued = accrued.checked_add(share).ok_or(AMMErrorCode::Overflow)?;
+        Ok(share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every field zeroed, None and false, a constant product pool
+    fn config() -> Config {
+        Config::deserialize(&mut &[0u8; Config::INIT_SPACE][..]).unwrap()
+    }
+
+    #[test]
+    fn queued_fee_survives_the_next_queue() {
+        let mut config = config();
+        config.fee = 30;
+        config.fee_delay = 100;
+
+        config.queue_fee(50, 1_000).unwrap();
+        assert_eq!((config.fee, config.pending_fee), (30, Some(50)));
+        assert_eq!(config.current_fee(1_099), 30);
+        assert_eq!(config.current_fee(1_100), 50);
+
+        // no swap ran to apply 50, queueing again must not fall back to 30
+        config.queue_fee(70, 1_200).unwrap();
+        assert_eq!((config.fee, config.pending_fee), (50, Some(70)));
+        assert_eq!(config.current_fee(1_299), 50);
+        assert_eq!(config.current_fee(1_300), 70);
+    }
+
+    #[test]
+    fn requeue_before_the_delay_replaces_the_pending_fee() {
+        let mut config = config();
+        config.fee = 30;
+        config.fee_delay = 100;
+
+        config.queue_fee(50, 1_000).unwrap();
+        config.queue_fee(70, 1_050).unwrap();
+        assert_eq!((config.fee, config.pending_fee, config.fee_effective_at), (30, Some(70), 1_150));
+    }
+
+    #[test]
+    fn no_delay_sets_the_fee_at_once() {
+        let mut config = config();
+        config.fee = 30;
+
+        config.queue_fee(50, 1_000).unwrap();
+        assert_eq!((config.fee, config.pending_fee), (50, None));
+    }
+
+    #[test]
+    fn volatility_is_only_tracked_in_dynamic_mode() {
+        let mut config = config();
+        config.volatility_cap = 1_000;
+        config.volatility_decay = 600;
+
+        // a 4x price move on a static pool leaves the accumulator alone
+        config.update_volatility(100, (1_000_000, 1_000_000), (500_000, 2_000_000)).unwrap();
+        assert_eq!((config.volatility, config.last_swap_ts), (0, 0));
+
+        config.dynamic_fee = true;
+        config.update_volatility(100, (1_000_000, 1_000_000), (500_000, 2_000_000)).unwrap();
+        assert_eq!((config.volatility, config.last_swap_ts), (30_000, 100));
     }
-    
 }
@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+// one per fee level, pools are derived from the tier so a pair has a single pool per tier
+#[account]
+#[derive(InitSpace)]
+pub struct FeeTier {
+    pub fee: u16,
+    // upgrade authority that created the tier, it administers every pool on the tier
+    pub admin: Pubkey,
+    pub bump: u8
+}
@@ -1,2 +1,10 @@
 pub mod config;
 pub use config::*;
+pub mod fee_tier;
+pub use fee_tier::*;
+pub mod cl_pool;
+pub use cl_pool::*;
+pub mod tick;
+pub use tick::*;
+pub mod position;
+pub use position::*;
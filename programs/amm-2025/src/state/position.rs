@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::{curves::Q64, error::AMMErrorCode, math::mul_div};
+
+// liquidity one owner provides between two ticks of a concentrated liquidity pool
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    // fee growth inside the range when the position was last settled
+    pub fee_growth_inside_x: u128,
+    pub fee_growth_inside_y: u128,
+    pub fees_owed_x: u64,
+    pub fees_owed_y: u64,
+    pub bump: u8
+}
+
+impl Position {
+    // books the fees earned since the last checkpoint
+    pub fn settle_fees(&mut self, inside_x: u128, inside_y: u128) -> Result<()> {
+        let earned_x = mul_div(inside_x.wrapping_sub(self.fee_growth_inside_x), self.liquidity, Q64)
+            .ok_or(AMMErrorCode::Overflow)?;
+        let earned_y = mul_div(inside_y.wrapping_sub(self.fee_growth_inside_y), self.liquidity, Q64)
+            .ok_or(AMMErrorCode::Overflow)?;
+
+        self.fees_owed_x = self.fees_owed_x.saturating_add(earned_x.min(u64::MAX as u128) as u64);
+        self.fees_owed_y = self.fees_owed_y.saturating_add(earned_y.min(u64::MAX as u128) as u64);
+        self.fee_growth_inside_x = inside_x;
+        self.fee_growth_inside_y = inside_y;
+        Ok(())
+    }
+}
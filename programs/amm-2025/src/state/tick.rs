@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+// an initialized tick of a concentrated liquidity pool. ticks form a sorted list through
+// prev/next so a swap can prove it crosses every one of them, they are never unlinked
+#[account]
+#[derive(InitSpace)]
+pub struct Tick {
+    pub pool: Pubkey,
+    pub index: i32,
+    pub prev: Option<i32>,
+    pub next: Option<i32>,
+    pub liquidity_gross: u128,
+    // added to the pool liquidity when the price crosses upwards, taken out downwards
+    pub liquidity_net: i128,
+    // fee growth on the side of the tick away from the current price
+    pub fee_growth_outside_x: u128,
+    pub fee_growth_outside_y: u128,
+    pub bump: u8
+}
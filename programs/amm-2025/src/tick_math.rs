@@ -0,0 +1,366 @@
+use anchor_lang::prelude::*;
+
+use crate::{curves::Q64, error::AMMErrorCode, math::{mul_div, mul_div_ceil}};
+
+// prices are 1.0001^tick, sqrt prices are kept as Q64.64
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_016;
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_992_447_577_915;
+
+// 2^64 / sqrt(1.0001)^(2^i)
+const TICK_RATIOS: [u128; 19] = [
+    0xfffcb933bd6fad37,
+    0xfff97272373d4132,
+    0xfff2e50f5f656932,
+    0xffe5caca7e10e4e6,
+    0xffcb9843d60f6159,
+    0xff973b41fa98c081,
+    0xff2ea16466c96a38,
+    0xfe5dee046a99a2a8,
+    0xfcbe86c7900a88ae,
+    0xf987a7253ac41317,
+    0xf3392b0822b70005,
+    0xe7159475a2c29b74,
+    0xd097f3bdfd2022b8,
+    0xa9f746462d870fdf,
+    0x70d869a156d2a1b8,
+    0x31be135f97d08fd9,
+    0x9aa508b5b7a84e1,
+    0x5d6af8dedb8119,
+    0x2216e584f5fa,
+];
+
+// 2^96 * sqrt(1.0001)^(2^i), positive ticks multiply up by these instead of inverting the negative side
+const TICK_RATIOS_UP: [u128; 19] = [
+    0x1000346d6ff11672ae55ad00f,
+    0x100068db8bac710cb295e9e1b,
+    0x1000d1b9c68abe5f76b30fb75,
+    0x1001a37e4a234cb0830516e51,
+    0x100347278ab0e92ada25ab460,
+    0x10068efb00a525480a5d7fdc2,
+    0x100d20a63b4173839df9daaa5,
+    0x101a4c11c742dd7729738df5e,
+    0x1034c35c31f64cfa6dc0d6de4,
+    0x106a34b78c8aaffbf81bed5a3,
+    0x10d72a6a46ccd8bce9ae771b1,
+    0x11b9a258e63928596dc757faa,
+    0x13a2e2bda04f8379f3cd17be5,
+    0x181954be69e0da8fe77f2ab42,
+    0x244c2655d185a029080252877,
+    0x525816eeb9f935b1c616779e8,
+    0x1a7c8d00b551684ff4d31ae065,
+    0x2bd893d0b2df7c97884590c66cd,
+    0x78278e1e19e448cf8b95d2152dccf,
+];
+
+// sqrt(1.0001^tick) as Q64.64, built from the bits of |tick|. positive ticks go largest factor first so
+// each floor is only carried through the small factors after it, inverting the Q64 ratio would lose
+// most of its digits at the top of the range
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!((MIN_TICK..=MAX_TICK).contains(&tick), AMMErrorCode::InvalidTick);
+
+    let abs = tick.unsigned_abs();
+    let mut ratio = Q64;
+    match tick > 0 {
+        true => {
+            for (i, factor) in TICK_RATIOS_UP.iter().enumerate().rev() {
+                if abs & (1 << i) != 0 {
+                    ratio = mul_div(ratio, *factor, 1 << 96).ok_or(AMMErrorCode::Overflow)?;
+                }
+            }
+        },
+        false => {
+            for (i, factor) in TICK_RATIOS.iter().enumerate() {
+                if abs & (1 << i) != 0 {
+                    ratio = (ratio * factor) >> 64;
+                }
+            }
+        }
+    }
+    Ok(ratio)
+}
+
+// log_sqrt(1.0001)(2) in Q32
+const LOG_B_2_X32: i128 = 59_543_866_431_248;
+// fraction bits of log2 worked out, the estimate below is good to within a tick with these
+const LOG2_PRECISION_BITS: u32 = 14;
+// error bounds of the estimate in Q64, 2^-14 / log2(b) plus 0.01 below and an upper bound of
+// the truncated fraction above
+const LOG_B_ERR_MARGIN_LOWER_X64: i128 = 184_467_440_737_095_516;
+const LOG_B_ERR_MARGIN_UPPER_X64: i128 = 15_793_534_762_490_258_745;
+
+// greatest tick whose sqrt price is not above `sqrt_price`. log2 of the Q64 price
+// converted to base sqrt(1.0001) narrows it to two ticks, one lookup picks between them
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    require!(
+        (MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price),
+        AMMErrorCode::InvalidSqrtPrice
+    );
+
+    // integer part from the most significant bit
+    let msb = 127 - sqrt_price.leading_zeros();
+    let log2_integer_x32 = (msb as i128 - 64) << 32;
+
+    // fraction by repeated squaring of the price normalised to [1, 2) in Q63,
+    // every square past 2 sets the next bit
+    let mut r = match msb >= 63 {
+        true => sqrt_price >> (msb - 63),
+        false => sqrt_price << (63 - msb)
+    };
+    let mut log2_fraction_x64: i128 = 0;
+    let mut bit: i128 = 1 << 63;
+    for _ in 0..LOG2_PRECISION_BITS {
+        r *= r;
+        let above_two = (r >> 127) as u32;
+        r >>= 63 + above_two;
+        log2_fraction_x64 += bit * above_two as i128;
+        bit >>= 1;
+    }
+
+    let log_b_x64 = (log2_integer_x32 + (log2_fraction_x64 >> 32)) * LOG_B_2_X32;
+    let tick_low = ((log_b_x64 - LOG_B_ERR_MARGIN_LOWER_X64) >> 64) as i32;
+    let tick_high = ((log_b_x64 + LOG_B_ERR_MARGIN_UPPER_X64) >> 64) as i32;
+
+    match tick_low == tick_high || sqrt_price_at_tick(tick_high)? > sqrt_price {
+        true => Ok(tick_low),
+        false => Ok(tick_high)
+    }
+}
+
+// x between two sqrt prices: L * (b - a) / (a * b)
+pub fn amount_x_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u64> {
+    let (low, high) = (sqrt_a.min(sqrt_b), sqrt_a.max(sqrt_b));
+    require!(low > 0, AMMErrorCode::InvalidSqrtPrice);
+
+    let amount = match round_up {
+        true => mul_div_ceil(liquidity, high - low, high).and_then(|v| mul_div_ceil(v, Q64, low)),
+        false => mul_div(liquidity, high - low, high).and_then(|v| mul_div(v, Q64, low))
+    }.ok_or(AMMErrorCode::Overflow)?;
+
+    u64::try_from(amount).map_err(|_| error!(AMMErrorCode::Overflow))
+}
+
+// y between two sqrt prices: L * (b - a)
+pub fn amount_y_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u64> {
+    let delta = sqrt_a.abs_diff(sqrt_b);
+
+    let amount = match round_up {
+        true => mul_div_ceil(liquidity, delta, Q64),
+        false => mul_div(liquidity, delta, Q64)
+    }.ok_or(AMMErrorCode::Overflow)?;
+
+    u64::try_from(amount).map_err(|_| error!(AMMErrorCode::Overflow))
+}
+
+// x and y backing `liquidity` between two ticks at the current price
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    liquidity: u128,
+    round_up: bool
+) -> Result<(u64, u64)> {
+    if sqrt_price <= sqrt_lower {
+        Ok((amount_x_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?, 0))
+    } else if sqrt_price >= sqrt_upper {
+        Ok((0, amount_y_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?))
+    } else {
+        Ok((
+            amount_x_delta(sqrt_price, sqrt_upper, liquidity, round_up)?,
+            amount_y_delta(sqrt_lower, sqrt_price, liquidity, round_up)?
+        ))
+    }
+}
+
+// sqrt price after `amount` of input, x pushes it down and y pushes it up, both rounded against the trader
+pub fn next_sqrt_price(sqrt_price: u128, liquidity: u128, amount: u64, x: bool) -> Result<u128> {
+    require!(liquidity > 0, AMMErrorCode::ZeroBalance);
+
+    match x {
+        // L * P / (L + dx * P)
+        true => {
+            let product = mul_div(amount as u128, sqrt_price, Q64).ok_or(AMMErrorCode::Overflow)?;
+            let denominator = liquidity.checked_add(product).ok_or(AMMErrorCode::Overflow)?;
+            mul_div_ceil(liquidity, sqrt_price, denominator).ok_or(error!(AMMErrorCode::Overflow))
+        },
+        // P + dy / L
+        false => {
+            let delta = mul_div(amount as u128, Q64, liquidity).ok_or(AMMErrorCode::Overflow)?;
+            sqrt_price.checked_add(delta).ok_or(error!(AMMErrorCode::Overflow))
+        }
+    }
+}
+
+pub struct SwapStep {
+    pub sqrt_price: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+}
+
+// one exact input step inside a single liquidity range, stops at `sqrt_target` if the input
+// is enough to reach it. `amount_in` excludes the fee (in basis points) which is charged on top
+pub fn swap_step(
+    sqrt_price: u128,
+    sqrt_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee: u16,
+    x: bool
+) -> Result<SwapStep> {
+    let fee_rate = fee as u128;
+    let amount_less_fee = (amount_remaining as u128 * (10000 - fee_rate) / 10000) as u64;
+
+    let amount_to_target = match x {
+        true => amount_x_delta(sqrt_target, sqrt_price, liquidity, true)?,
+        false => amount_y_delta(sqrt_price, sqrt_target, liquidity, true)?
+    };
+
+    let reached = amount_less_fee >= amount_to_target;
+    let next = match reached {
+        true => sqrt_target,
+        false => next_sqrt_price(sqrt_price, liquidity, amount_less_fee, x)?
+    };
+
+    let (amount_in, amount_out) = match x {
+        true => (
+            amount_x_delta(next, sqrt_price, liquidity, true)?,
+            amount_y_delta(next, sqrt_price, liquidity, false)?
+        ),
+        false => (
+            amount_y_delta(sqrt_price, next, liquidity, true)?,
+            amount_x_delta(sqrt_price, next, liquidity, false)?
+        )
+    };
+
+    // the price was already rounded against the trader, rounding the input up on top can pass what
+    // a step that stops short was given when no fee is held back
+    let amount_in = match reached {
+        true => amount_in,
+        false => amount_in.min(amount_less_fee)
+    };
+
+    // a step that stops short consumes the whole remaining input, the rest is fee
+    let fee = match reached {
+        true => ((amount_in as u128 * fee_rate).div_ceil(10000 - fee_rate) as u64).min(amount_remaining - amount_in),
+        false => amount_remaining - amount_in
+    };
+
+    Ok(SwapStep { sqrt_price: next, amount_in, amount_out, fee })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Orca Whirlpool's sqrt_price_from_tick_index, also Q64.64 over the same tick range
+    const WHIRLPOOL_PLUS_ONE: u128 = 18_447_666_387_855_959_850;
+    const WHIRLPOOL_MINUS_ONE: u128 = 18_445_821_805_675_392_311;
+    const WHIRLPOOL_MAX: u128 = 79_226_673_515_401_279_992_447_579_055;
+    const WHIRLPOOL_MIN: u128 = 4_295_048_016;
+
+    const FEE_BPS: u16 = 30;
+
+    #[test]
+    fn sqrt_price_matches_whirlpool() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), Q64);
+        assert_eq!(sqrt_price_at_tick(1).unwrap(), WHIRLPOOL_PLUS_ONE);
+        assert_eq!(sqrt_price_at_tick(-1).unwrap(), WHIRLPOOL_MINUS_ONE);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap(), WHIRLPOOL_MIN);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE);
+
+        // whirlpool carries a 256 bit intermediate, ours floors at each bit and lands a hair under
+        let max = sqrt_price_at_tick(MAX_TICK).unwrap();
+        assert_eq!(max, MAX_SQRT_PRICE);
+        assert!(max <= WHIRLPOOL_MAX && WHIRLPOOL_MAX - max < 1 << 12);
+
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_inverts() {
+        for tick in [MIN_TICK, MIN_TICK + 1, -1, 0, 1, MAX_TICK - 1, MAX_TICK] {
+            let price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(price).unwrap(), tick);
+            // anything under a tick's price belongs to the tick below
+            if tick > MIN_TICK {
+                assert_eq!(tick_at_sqrt_price(price - 1).unwrap(), tick - 1);
+            }
+        }
+        assert_eq!(tick_at_sqrt_price(MIN_SQRT_PRICE).unwrap(), MIN_TICK);
+        assert_eq!(tick_at_sqrt_price(MAX_SQRT_PRICE).unwrap(), MAX_TICK);
+        assert!(tick_at_sqrt_price(MIN_SQRT_PRICE - 1).is_err());
+        assert!(tick_at_sqrt_price(MAX_SQRT_PRICE + 1).is_err());
+
+        // the log2 estimate across the whole range, at both edges of a tick and in between
+        for tick in (MIN_TICK..MAX_TICK).step_by(997) {
+            let (price, next) = (sqrt_price_at_tick(tick).unwrap(), sqrt_price_at_tick(tick + 1).unwrap());
+            assert_eq!(tick_at_sqrt_price(price).unwrap(), tick);
+            assert_eq!(tick_at_sqrt_price(price + (next - price) / 2).unwrap(), tick);
+            assert_eq!(tick_at_sqrt_price(next - 1).unwrap(), tick);
+        }
+    }
+
+    #[test]
+    fn sqrt_price_is_monotonic() {
+        let mut previous = sqrt_price_at_tick(MIN_TICK).unwrap();
+        for tick in (MIN_TICK + 1..=MAX_TICK).step_by(997) {
+            let price = sqrt_price_at_tick(tick).unwrap();
+            assert!(price > previous);
+            previous = price;
+        }
+    }
+
+    #[test]
+    fn swap_step_rounds_against_the_trader() {
+        let liquidity = 1_000_000_000_000u128;
+        let price = sqrt_price_at_tick(0).unwrap();
+
+        // a fee free tier keeps nothing back, the step can not round its input past what it was given
+        for (x, fee) in [(true, 0), (true, FEE_BPS), (false, 0), (false, FEE_BPS)] {
+            let far = sqrt_price_at_tick(if x { -1000 } else { 1000 }).unwrap();
+            for amount in [1u64, 7, 999, 1_000_000, 1_000_001, 123_456_789] {
+                // stops short of the target, whatever the price move does not use is kept as fee
+                let step = swap_step(price, far, liquidity, amount, fee, x).unwrap();
+                assert_ne!(step.sqrt_price, far);
+                assert_eq!(step.amount_in + step.fee, amount);
+
+                // the price moved no further than the input pays for, the output no more than the move gives
+                let (paid_for, owed) = match x {
+                    true => (
+                        amount_x_delta(step.sqrt_price, price, liquidity, false).unwrap(),
+                        amount_y_delta(step.sqrt_price, price, liquidity, false).unwrap()
+                    ),
+                    false => (
+                        amount_y_delta(price, step.sqrt_price, liquidity, false).unwrap(),
+                        amount_x_delta(price, step.sqrt_price, liquidity, false).unwrap()
+                    )
+                };
+                assert!(paid_for <= step.amount_in);
+                assert!(step.amount_out <= owed);
+            }
+
+            // enough input to reach the target, the step stops there and leaves the rest
+            let near = sqrt_price_at_tick(if x { -1 } else { 1 }).unwrap();
+            let amount = 1_000_000_000;
+            let step = swap_step(price, near, liquidity, amount, fee, x).unwrap();
+            assert_eq!(step.sqrt_price, near);
+            assert!(step.amount_in + step.fee < amount);
+            let (exact_in, exact_out) = match x {
+                true => (
+                    amount_x_delta(near, price, liquidity, true).unwrap(),
+                    amount_y_delta(near, price, liquidity, false).unwrap()
+                ),
+                false => (
+                    amount_y_delta(price, near, liquidity, true).unwrap(),
+                    amount_x_delta(price, near, liquidity, false).unwrap()
+                )
+            };
+            assert_eq!((step.amount_in, step.amount_out), (exact_in, exact_out));
+            assert!(step.amount_out < step.amount_in);
+            assert!(step.fee as u128 * (10000 - fee as u128) >= step.amount_in as u128 * fee as u128);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::Mint as MintState,
+    },
+    token_interface::{close_account, sync_native, CloseAccount, Mint, SyncNative},
+};
+
+use crate::error::AMMErrorCode;
+
+// extensions a pooled mint may carry, anything else (permanent delegate, non transferable,
+// transfer hooks, default frozen accounts, ...) could move or freeze vault funds behind the pool's back.
+// interest bearing mints only change the UI amount, raw balances are what the pool trades
+const SUPPORTED_EXTENSIONS: [ExtensionType; 9] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+pub fn assert_supported_mint(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    for extension in state.get_extension_types()? {
+        require!(SUPPORTED_EXTENSIONS.contains(&extension), AMMErrorCode::UnsupportedMintExtension);
+    }
+    Ok(())
+}
+
+// transfer fee the mint withholds when `amount` is sent this epoch
+pub fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(error!(AMMErrorCode::Overflow)),
+        Err(_) => Ok(0)
+    }
+}
+
+// what actually lands in the destination account
+pub fn amount_after_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    Ok(amount - transfer_fee(mint, amount)?)
+}
+
+// what has to be sent so that `amount` lands in the destination account
+pub fn amount_before_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) if amount > 0 => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(AMMErrorCode::Overflow)?,
+        _ => 0
+    };
+    amount.checked_add(fee).ok_or(error!(AMMErrorCode::Overflow))
+}
+
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+// side of the pool holding wrapped SOL, true for x
+pub fn native_side(mint_x: &Pubkey, mint_y: &Pubkey) -> Result<bool> {
+    match (is_native_mint(mint_x), is_native_mint(mint_y)) {
+        (true, _) => Ok(true),
+        (_, true) => Ok(false),
+        _ => err!(AMMErrorCode::NoNativeMint)
+    }
+}
+
+// moves lamports from the owner into their WSOL account and syncs the token amount
+pub fn wrap_sol<'info>(
+    owner: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    amount: u64
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from: owner,
+        to: account.clone(),
+    };
+    transfer(CpiContext::new(system_program, cpi_accounts), amount)?;
+
+    sync_native(CpiContext::new(token_program, SyncNative { account }))
+}
+
+// closes the owner's WSOL account, the whole balance and the rent come back as lamports
+pub fn unwrap_sol<'info>(
+    owner: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    token_program: AccountInfo<'info>
+) -> Result<()> {
+    let cpi_accounts = CloseAccount {
+        account,
+        destination: owner.clone(),
+        authority: owner,
+    };
+    close_account(CpiContext::new(token_program, cpi_accounts))
+}
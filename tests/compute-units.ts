@@ -0,0 +1,179 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+  ComputeBudgetProgram,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccount,
+  createMint,
+  getAssociatedTokenAddressSync,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { Amm2025 } from "../target/types/amm_2025";
+
+// the default budget of a single instruction
+const DEFAULT_COMPUTE_UNITS = 200_000;
+const FEE = 30;
+
+const BPF_LOADER_UPGRADEABLE = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+
+// single sided deposits price the zap with a bisection of trial swaps on the
+// curves without a closed form, these keep it inside the default budget
+describe("compute units", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.amm2025 as Program<Amm2025>;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const user = payer.publicKey;
+
+  const [feeTier] = PublicKey.findProgramAddressSync(
+    [Buffer.from("fee_tier"), new BN(FEE).toArrayLike(Buffer, "le", 2)],
+    program.programId
+  );
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    BPF_LOADER_UPGRADEABLE
+  );
+
+  let mintX: PublicKey;
+  let mintY: PublicKey;
+
+  const expiration = () => new BN(Math.floor(Date.now() / 1000) + 600);
+
+  // simulates the instruction under the full budget and returns what it consumed
+  const unitsOf = async (ix: TransactionInstruction) => {
+    const tx = new Transaction().add(
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+      ix
+    );
+    tx.feePayer = user;
+    const { value } = await provider.connection.simulateTransaction(tx, [payer]);
+    expect(value.err, (value.logs ?? []).join("\n")).to.be.null;
+    return value.unitsConsumed;
+  };
+
+  const pool = (curveIndex: number) => {
+    const [config] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("config"),
+        mintX.toBuffer(),
+        mintY.toBuffer(),
+        feeTier.toBuffer(),
+        Buffer.from([curveIndex]),
+      ],
+      program.programId
+    );
+    const [auth] = PublicKey.findProgramAddressSync(
+      [Buffer.from("auth"), config.toBuffer()],
+      program.programId
+    );
+    const [mintLp] = PublicKey.findProgramAddressSync(
+      [Buffer.from("liquidity"), config.toBuffer()],
+      program.programId
+    );
+    return {
+      user,
+      mintX,
+      mintY,
+      mintLp,
+      vaultX: getAssociatedTokenAddressSync(mintX, auth, true),
+      vaultY: getAssociatedTokenAddressSync(mintY, auth, true),
+      userX: getAssociatedTokenAddressSync(mintX, user),
+      userY: getAssociatedTokenAddressSync(mintY, user),
+      userLp: getAssociatedTokenAddressSync(mintLp, user),
+      auth,
+      config,
+      oracle: null,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+  };
+
+  before(async () => {
+    // the pair has to be ordered, x below y
+    const mints = [
+      await createMint(provider.connection, payer, user, null, 6),
+      await createMint(provider.connection, payer, user, null, 6),
+    ].sort((a, b) => a.toBuffer().compare(b.toBuffer()));
+    [mintX, mintY] = mints;
+
+    for (const mint of mints) {
+      const account = await createAssociatedTokenAccount(provider.connection, payer, mint, user);
+      await mintTo(provider.connection, payer, mint, account, payer, 1_000_000_000_000_000);
+    }
+
+    if (!(await program.account.feeTier.fetchNullable(feeTier))) {
+      await program.methods
+        .createFeeTier(FEE)
+        .accountsStrict({
+          admin: user,
+          feeTier,
+          program: program.programId,
+          programData,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  const curves = [
+    { name: "stable swap", index: 1, curveType: { stableSwap: {} }, amp: 100, weightX: 0 },
+    { name: "weighted", index: 2, curveType: { weighted: {} }, amp: 0, weightX: 8000 },
+  ];
+
+  for (const { name, index, curveType, amp, weightX } of curves) {
+    it(`keeps ${name} single sided liquidity inside the default budget`, async () => {
+      const accounts = pool(index);
+      const { user: initializer, userX, userY, userLp, ...rest } = accounts;
+      await program.methods
+        .initialize({
+          curveType,
+          amp: new BN(amp),
+          weightX,
+          oracle: { maxStaleness: new BN(0), maxConfidence: 0, spread: 0 },
+          renounceAuthority: false,
+        })
+        .accountsStrict({ initializer, feeTier, ...rest })
+        .rpc();
+
+      const vaultLp = getAssociatedTokenAddressSync(accounts.mintLp, accounts.auth, true);
+      await program.methods
+        .deposit(new BN(1), new BN(1_000_000_000_000), new BN(3_000_000_000_000), expiration(), false)
+        .accountsStrict({ ...accounts, vaultLp })
+        .rpc();
+
+      for (const isX of [true, false]) {
+        for (const amount of [1_000_000, 50_000_000_000]) {
+          const units = await unitsOf(
+            await program.methods
+              .depositSingle(isX, new BN(amount), new BN(0), expiration(), false)
+              .accountsStrict({ ...accounts, vaultLp })
+              .instruction()
+          );
+          expect(units).to.be.below(DEFAULT_COMPUTE_UNITS);
+        }
+
+        const units = await unitsOf(
+          await program.methods
+            .withdrawSingle(new BN(1_000_000_000), isX, new BN(0), expiration(), false)
+            .accountsStrict(accounts)
+            .instruction()
+        );
+        expect(units).to.be.below(DEFAULT_COMPUTE_UNITS);
+      }
+    });
+  }
+});